@@ -0,0 +1,105 @@
+// helpers for the binary `Heatmap` format, which is made up of little-endian
+// fixed width integers and LEB128 varints
+
+use super::LoadError;
+use std::io;
+use std::io::{Read, Write};
+
+pub const MAGIC: &[u8; 4] = b"HMAP";
+pub const VERSION: u8 = 1;
+
+/// write a fixed width little-endian u32
+pub fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+/// write a fixed width little-endian u64
+pub fn write_u64<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+/// write a LEB128 encoded varint
+pub fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+pub struct Reader<R> {
+    reader: R,
+    position: usize,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            position: 0,
+        }
+    }
+
+    /// returns an error for the current position within the input
+    pub fn error(&self) -> LoadError {
+        LoadError::Binary { offset: self.position }
+    }
+
+    /// read exactly enough bytes to fill the buffer
+    pub fn bytes(&mut self, buffer: &mut [u8]) -> Result<(), LoadError> {
+        self.reader.read_exact(buffer)?;
+        self.position += buffer.len();
+        Ok(())
+    }
+
+    /// read a single byte
+    pub fn u8(&mut self) -> Result<u8, LoadError> {
+        let mut buffer = [0; 1];
+        self.bytes(&mut buffer)?;
+        Ok(buffer[0])
+    }
+
+    /// read a fixed width little-endian u32
+    pub fn u32(&mut self) -> Result<u32, LoadError> {
+        let mut buffer = [0; 4];
+        self.bytes(&mut buffer)?;
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    /// read a fixed width little-endian u64
+    pub fn u64(&mut self) -> Result<u64, LoadError> {
+        let mut buffer = [0; 8];
+        self.bytes(&mut buffer)?;
+        Ok(u64::from_le_bytes(buffer))
+    }
+
+    /// read a LEB128 encoded varint, rejecting any which overflow a u64
+    pub fn varint(&mut self) -> Result<u64, LoadError> {
+        let error = self.error();
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift == 63 && byte > 1 {
+                return Err(error);
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// ensure that the end of the input has been reached
+    pub fn finish(&mut self) -> Result<(), LoadError> {
+        let mut buffer = [0; 1];
+        match self.reader.read(&mut buffer)? {
+            0 => Ok(()),
+            _ => Err(self.error()),
+        }
+    }
+}
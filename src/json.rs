@@ -0,0 +1,126 @@
+// a minimal JSON reader, supporting only the subset of JSON which is produced
+// by `Heatmap::to_json`: objects, arrays, unescaped strings and unsigned
+// integers
+
+use super::LoadError;
+
+pub struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Parser<'a> {
+        Parser { input, position: 0 }
+    }
+
+    /// returns an error for the current position within the input
+    pub fn error(&self) -> LoadError {
+        LoadError::Json { offset: self.position }
+    }
+
+    // returns the next non-whitespace byte without consuming it
+    fn peek(&mut self) -> Option<u8> {
+        let bytes = self.input.as_bytes();
+        while self.position < bytes.len() && bytes[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+        bytes.get(self.position).cloned()
+    }
+
+    // consume the next non-whitespace byte if it matches
+    fn expect(&mut self, byte: u8) -> Result<(), LoadError> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// parse a string, which may not contain escape sequences
+    pub fn string(&mut self) -> Result<&'a str, LoadError> {
+        self.expect(b'"')?;
+        let start = self.position;
+        match self.input[start..].find(['"', '\\']) {
+            Some(length) if self.input.as_bytes()[start + length] == b'"' => {
+                self.position = start + length + 1;
+                Ok(&self.input[start..start + length])
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    /// parse an unsigned integer
+    pub fn number(&mut self) -> Result<u64, LoadError> {
+        self.peek();
+        let start = self.position;
+        let length = self.input[start..]
+            .bytes()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        match self.input[start..start + length].parse() {
+            Ok(number) => {
+                self.position += length;
+                Ok(number)
+            }
+            Err(_) => Err(self.error()),
+        }
+    }
+
+    /// parse an object, calling `f` to parse the value for each key
+    pub fn object<F>(&mut self, mut f: F) -> Result<(), LoadError>
+    where
+        F: FnMut(&mut Parser<'a>, &'a str) -> Result<(), LoadError>,
+    {
+        self.expect(b'{')?;
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(());
+        }
+        loop {
+            let key = self.string()?;
+            self.expect(b':')?;
+            f(self, key)?;
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    /// parse an array, calling `f` to parse each element
+    pub fn array<F>(&mut self, mut f: F) -> Result<(), LoadError>
+    where
+        F: FnMut(&mut Parser<'a>) -> Result<(), LoadError>,
+    {
+        self.expect(b'[')?;
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(());
+        }
+        loop {
+            f(self)?;
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    /// ensure that only whitespace remains in the input
+    pub fn finish(&mut self) -> Result<(), LoadError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error()),
+        }
+    }
+}
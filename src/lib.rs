@@ -7,9 +7,6 @@
 //! * report time-series percentiles
 //! * auto-slicing by record time
 //!
-//! # Future work
-//! * more efficient serialization format
-//!
 //! # Usage
 //! Create a heatmap. Insert values over time. Profit.
 //!
@@ -28,14 +25,524 @@
 extern crate histogram;
 extern crate time;
 
+mod binary;
+mod json;
+#[cfg(feature = "image")]
+mod png;
+
 use histogram::Histogram;
+use std::cmp;
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter};
 use std::io::prelude::Write;
+use std::mem;
+use std::num::ParseIntError;
+use std::ops::{Add, AddAssign, Range};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+/// Errors which may occur when recording into or reading from a `Heatmap`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeatmapError {
+    /// the time is before the start of the `Heatmap`
+    SampleTooEarly,
+    /// the time is at or after the stop of the `Heatmap`
+    SampleTooLate,
+    /// the value is larger than the configured `max_value`
+    ValueOutOfRange,
+    /// the percentile is not within the range 0.0 to 100.0
+    InvalidPercentile,
+    /// the start of the time window is not before its stop
+    InvalidWindow,
+    /// the `Heatmap`s do not share the same slice and value geometry
+    Incompatible,
+    /// the factor must be non-zero and evenly divide `num_slices`
+    InvalidFactor,
+    /// the `Config` could not be used to build a `Heatmap`
+    InvalidConfig(ConfigError),
+}
+
+impl fmt::Display for HeatmapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HeatmapError::SampleTooEarly => f.write_str("sample too early"),
+            HeatmapError::SampleTooLate => f.write_str("sample too late"),
+            HeatmapError::ValueOutOfRange => f.write_str("sample value out of range"),
+            HeatmapError::InvalidPercentile => f.write_str("percentile out of range"),
+            HeatmapError::InvalidWindow => f.write_str("window start must be before stop"),
+            HeatmapError::Incompatible => f.write_str("heatmaps are incompatible"),
+            HeatmapError::InvalidFactor => f.write_str("factor must evenly divide num_slices"),
+            HeatmapError::InvalidConfig(e) => write!(f, "invalid config: {}", e),
+        }
+    }
+}
+
+impl Error for HeatmapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            HeatmapError::InvalidConfig(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for HeatmapError {
+    fn from(e: ConfigError) -> HeatmapError {
+        HeatmapError::InvalidConfig(e)
+    }
+}
+
+/// Errors which may occur when merging one `Heatmap` into another
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeError {
+    /// the `Heatmap`s do not share the same `precision` and `max_value`
+    Incompatible,
+    /// the `Heatmap`s do not share the same `num_slices` and `slice_duration`
+    Misaligned,
+    /// some samples fall outside the time range of the destination
+    OutOfRange {
+        /// the number of samples which would have been dropped
+        dropped: u64,
+    },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MergeError::Incompatible => f.write_str("heatmaps are incompatible"),
+            MergeError::Misaligned => f.write_str("heatmaps have different slices"),
+            MergeError::OutOfRange { dropped } => {
+                write!(f, "{} samples outside of time range", dropped)
+            }
+        }
+    }
+}
+
+impl Error for MergeError {}
+
+/// Errors which may occur when building a `Heatmap` from a `Config`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// `num_slices` must be greater than zero
+    NumSlices,
+    /// `slice_duration` must be greater than zero
+    SliceDuration,
+    /// `precision` must be within the range supported by `Histogram`
+    Precision,
+    /// `max_value` must be at least 10^`precision`
+    MaxValue,
+    /// `max_memory` is too small to hold the `Histogram` for each `Slice`
+    MaxMemory,
+    /// `boundaries` must have at least two strictly increasing times, and
+    /// can not be used in circular mode
+    Boundaries,
+    /// the `Heatmap` must stop no later than `u64::MAX`
+    TimeSpan,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match *self {
+            ConfigError::NumSlices => "num_slices must be greater than zero",
+            ConfigError::SliceDuration => "slice_duration must be greater than zero",
+            ConfigError::Precision => "precision out of range",
+            ConfigError::MaxValue => "max_value must be at least 10^precision",
+            ConfigError::MaxMemory => "max_memory too small",
+            ConfigError::Boundaries => "boundaries must be strictly increasing and not circular",
+            ConfigError::TimeSpan => "heatmap would stop after u64::MAX",
+        };
+        f.write_str(description)
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Errors which may occur when loading a saved `Heatmap`
+#[derive(Debug)]
+pub enum LoadError {
+    /// the file could not be read
+    Io(io::Error),
+    /// a token could not be parsed as a number
+    Parse {
+        /// the line number, starting from 1
+        line: usize,
+        /// the underlying parse error
+        error: ParseIntError,
+    },
+    /// a line has the wrong number of tokens, or describes an invalid `Heatmap`
+    Malformed {
+        /// the line number, starting from 1
+        line: usize,
+    },
+    /// the JSON is malformed, or describes an invalid `Heatmap`
+    Json {
+        /// the byte offset of the error within the input
+        offset: usize,
+    },
+    /// the binary data is malformed, or describes an invalid `Heatmap`
+    Binary {
+        /// the byte offset of the error within the input
+        offset: usize,
+    },
+    /// the binary data was written with an unknown format version
+    UnsupportedVersion {
+        /// the format version found in the input
+        version: u8,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io(ref e) => write!(f, "io error: {}", e),
+            LoadError::Parse { line, ref error } => {
+                write!(f, "parse error on line {}: {}", line, error)
+            }
+            LoadError::Malformed { line } => write!(f, "malformed heatmap on line {}", line),
+            LoadError::Json { offset } => write!(f, "malformed json at offset {}", offset),
+            LoadError::Binary { offset } => write!(f, "malformed binary at offset {}", offset),
+            LoadError::UnsupportedVersion { version } => {
+                write!(f, "unsupported binary format version {}", version)
+            }
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            LoadError::Io(ref e) => Some(e),
+            LoadError::Parse { ref error, .. } => Some(error),
+            LoadError::Malformed { .. } |
+            LoadError::Json { .. } |
+            LoadError::Binary { .. } |
+            LoadError::UnsupportedVersion { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+// parse a single whitespace-delimited token from a saved `Heatmap`
+fn parse_token<T: FromStr<Err = ParseIntError>>(token: &str, line: usize) -> Result<T, LoadError> {
+    token.parse().map_err(|error| LoadError::Parse { line, error })
+}
+
+// parse a `time value count` record from a saved `Heatmap` or a stream
+fn parse_record(line: &str, line_number: usize) -> Result<(u64, u64, u64), LoadError> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() != 3 {
+        return Err(LoadError::Malformed { line: line_number });
+    }
+    Ok((
+        parse_token(tokens[0], line_number)?,
+        parse_token(tokens[1], line_number)?,
+        parse_token(tokens[2], line_number)?,
+    ))
+}
+
+// update a 64-bit FNV-1a hash with the little-endian bytes of a value
+fn fnv1a(mut hash: u64, value: u64) -> u64 {
+    for byte in &value.to_le_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// find the smallest value which is counted in each bucket of the empty
+// histogram. The bucket math is private to `Histogram` and the value reported
+// for some buckets is counted in the next one, so every reported value is
+// recorded once and, as values are counted in buckets in ascending order,
+// walking the counts finds a value in most buckets. Those buckets are then
+// marked with their index plus one, and the lower bound of each bucket is
+// found by binary search, marking any bucket which none of the reported
+// values are counted in as it is found. A bucket which no value is counted in
+// shares the lower bound of the next
+fn lower_bounds(mut probe: Histogram, reported: &[u64]) -> Vec<u64> {
+    for value in reported {
+        let _ = probe.increment(*value);
+    }
+
+    let mut values = reported.iter().cloned();
+    let found: Vec<Option<u64>> = probe
+        .into_iter()
+        .map(|bucket| {
+            let first = if bucket.count() > 0 { values.next() } else { None };
+            for _ in 1..bucket.count() {
+                values.next();
+            }
+            first
+        })
+        .collect();
+
+    probe.clear();
+    for (index, value) in found.iter().enumerate() {
+        if let Some(value) = *value {
+            let _ = probe.increment_by(value, index as u64 + 1);
+        }
+    }
+
+    // the nearest found value at or after each bucket
+    let mut next = vec![0; found.len()];
+    let mut last = reported.last().cloned().unwrap_or(0);
+    for index in (0..found.len()).rev() {
+        last = found[index].unwrap_or(last);
+        next[index] = last;
+    }
+
+    let mut lower = vec![0; found.len()];
+    // the lower bound and mark of the last bucket which a value is counted in
+    let (mut low, mut mark) = (0, 1);
+    for index in 1..found.len() {
+        let (mut low_value, mut high) = (low, next[index]);
+        while high - low_value > 1 {
+            let middle = low_value + (high - low_value) / 2;
+            if probe.get(middle) == Some(mark) {
+                low_value = middle;
+            } else {
+                high = middle;
+            }
+        }
+        lower[index] = high;
+        let counted = match probe.get(high) {
+            Some(0) => {
+                let _ = probe.increment_by(high, index as u64 + 1);
+                true
+            }
+            Some(value) => value == index as u64 + 1,
+            None => false,
+        };
+        if counted {
+            low = high;
+            mark = index as u64 + 1;
+        }
+    }
+    lower
+}
+
+// collect the `(value, count)` of each bucket in the histogram which has a
+// count, using the lower bounds from `lower_bounds` so that the value is
+// counted in the same bucket
+fn occupied_buckets(histogram: &Histogram, lower: &[u64]) -> Vec<(u64, u64)> {
+    histogram
+        .into_iter()
+        .zip(lower)
+        .filter(|(bucket, _)| bucket.count() > 0)
+        .map(|(bucket, value)| (*value, bucket.count()))
+        .collect()
+}
+
+// record the buckets of one histogram in another with the same config, as
+// `Histogram::merge` records each bucket at its reported value, which for some
+// buckets is counted in the next one
+fn merge_histogram(histogram: &mut Histogram, other: &Histogram, lower: &[u64]) {
+    for (value, count) in occupied_buckets(other, lower) {
+        let _ = histogram.increment_by(value, count);
+    }
+}
+
+// infer the precision of a histogram from the reported value of each bucket,
+// as `Histogram` does not expose its config. Each value below the linear limit
+// for the precision has its own bucket, unless the `max_value` is lower still,
+// in which case every bucket is linear and the precision is its digits less one
+fn infer_precision(values: &[u64]) -> Option<u32> {
+    let max_value = *values.last()?;
+    let linear = values
+        .iter()
+        .enumerate()
+        .take_while(|&(index, &value)| value == index as u64)
+        .count();
+
+    (1..8).find(|&precision| {
+        let buckets_inner = 10_u64.pow(precision);
+        let linear_max = 1 << (64 - buckets_inner.leading_zeros());
+        if linear == values.len() {
+            buckets_inner <= max_value && max_value <= linear_max
+        } else {
+            linear as u64 == linear_max + 1
+        }
+    })
+}
+
+// parse the config object produced by `Heatmap::to_json` and build an empty
+// `Heatmap` from it, returning `None` if the config is invalid
+fn parse_json_config(parser: &mut json::Parser) -> Result<Option<Heatmap>, LoadError> {
+    let mut fields = [None; 6];
+    let mut boundaries = None;
+
+    parser.object(|parser, key| {
+        let field = match key {
+            "precision" => 0,
+            "max_memory" => 1,
+            "max_value" => 2,
+            "slice_duration" => 3,
+            "num_slices" => 4,
+            "start" => 5,
+            "boundaries" => {
+                let mut times = Vec::new();
+                parser.array(|parser| {
+                    times.push(parser.number()?);
+                    Ok(())
+                })?;
+                boundaries = Some(times);
+                return Ok(());
+            }
+            _ => return Err(parser.error()),
+        };
+        fields[field] = Some(parser.number()?);
+        Ok(())
+    })?;
+
+    let mut values = [0; 6];
+    for (value, field) in values.iter_mut().zip(fields.iter()) {
+        *value = field.ok_or_else(|| parser.error())?;
+    }
+
+    if values[0] > u64::from(u32::MAX) || values[1] > u64::from(u32::MAX) {
+        return Err(parser.error());
+    }
+
+    let mut config = Heatmap::configure()
+        .precision(values[0] as u32)
+        .max_memory(values[1] as u32)
+        .max_value(values[2])
+        .slice_duration(values[3])
+        .num_slices(values[4] as usize)
+        .start(values[5]);
+    if let Some(boundaries) = boundaries {
+        config = config.boundaries(boundaries);
+    }
+
+    Ok(config.build().ok())
+}
+
+// calculate several percentiles from a single pass over the buckets of a
+// histogram, matching the results of `Histogram::percentile`, which walks up
+// from the smallest value below the 50th percentile and down from the largest
+// value otherwise. Percentiles of an empty histogram are 0
+fn histogram_percentiles(histogram: &Histogram, percentiles: &[f64]) -> Vec<u64> {
+    let (values, counts): (Vec<u64>, Vec<u64>) = histogram
+        .into_iter()
+        .map(|bucket| (bucket.value(), bucket.count()))
+        .unzip();
+    bucket_percentiles(&values, &counts, percentiles)
+}
+
+// calculate several percentiles from the value and count of each bucket, as
+// for `histogram_percentiles`
+fn bucket_percentiles(values: &[u64], counts: &[u64], percentiles: &[f64]) -> Vec<u64> {
+    let mut cumulative = Vec::with_capacity(counts.len());
+    let mut total = 0;
+    for count in counts {
+        total += count;
+        cumulative.push(total);
+    }
+
+    if total == 0 {
+        return vec![0; percentiles.len()];
+    }
+
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let rank = cmp::min((total as f64 * (percentile / 100.0)).ceil() as u64, total);
+            let index = if *percentile < 50.0 {
+                // the first bucket which reaches the rank
+                let need = cmp::max(rank, 1);
+                cumulative.partition_point(|&c| c < need)
+            } else {
+                // the last bucket with enough entries at or above it
+                let need = cmp::max(total - rank, 1);
+                cumulative.partition_point(|&c| c <= total - need)
+            };
+            values[index]
+        })
+        .collect()
+}
+
+// calculate a percentile from the buckets of a histogram, interpolating
+// linearly within the bucket which contains the percentile rank. Each bucket is
+// taken to span from the value of the bucket before it up to its own value.
+// The percentile of an empty histogram is 0
+fn interpolated_percentile(histogram: &Histogram, values: &[u64], percentile: f64) -> f64 {
+    let total = histogram.entries();
+    if total == 0 {
+        return 0.0;
+    }
+    let rank = total as f64 * percentile / 100.0;
+
+    let mut cumulative = 0;
+    let mut lower = 0;
+    for (bucket, &upper) in histogram.into_iter().zip(values) {
+        let count = bucket.count();
+        if count > 0 && (cumulative + count) as f64 >= rank {
+            let fraction = (rank - cumulative as f64) / count as f64;
+            return lower as f64 + fraction.max(0.0) * (upper - lower) as f64;
+        }
+        cumulative += count;
+        lower = upper;
+    }
+    lower as f64
+}
+
+/// The unit of the timestamps recorded into a `Heatmap`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// nanoseconds, as returned by `time::precise_time_ns`
+    Nanos,
+    /// microseconds
+    Micros,
+    /// milliseconds
+    Millis,
+    /// seconds
+    Secs,
+}
+
+impl TimeUnit {
+    // the number of nanoseconds in one of the unit
+    fn nanos(self) -> u64 {
+        match self {
+            TimeUnit::Nanos => 1,
+            TimeUnit::Micros => 1_000,
+            TimeUnit::Millis => 1_000_000,
+            TimeUnit::Secs => 1_000_000_000,
+        }
+    }
+
+    // the number of the unit in one second
+    fn per_second(self) -> u64 {
+        1_000_000_000 / self.nanos()
+    }
+
+    // a clock which counts in the unit
+    fn clock(self) -> fn() -> u64 {
+        match self {
+            TimeUnit::Nanos => time::precise_time_ns,
+            TimeUnit::Micros => || time::precise_time_ns() / 1_000,
+            TimeUnit::Millis => || time::precise_time_ns() / 1_000_000,
+            TimeUnit::Secs => || time::precise_time_ns() / 1_000_000_000,
+        }
+    }
+}
 
 /// A configuration struct for building custom `Heatmap`s.
-#[derive(Clone, Copy)]
+///
+/// `Config` is `Clone` but no longer `Copy`, as it owns any slice
+/// `boundaries`, so code which copied it out of `Heatmap::config` must now
+/// call `clone`
+#[derive(Clone, Debug)]
 pub struct Config {
     precision: u32,
     max_memory: u32,
@@ -43,6 +550,22 @@ pub struct Config {
     slice_duration: u64,
     num_slices: usize,
     start: u64,
+    circular: bool,
+    boundaries: Option<Vec<u64>>,
+    clock: fn() -> u64,
+    time_unit: TimeUnit,
+    strict: bool,
+    explicit: Explicit,
+}
+
+// which of the settings whose defaults are derived when the `Heatmap` is
+// built have been set explicitly, and so must be kept
+#[derive(Clone, Copy, Debug, Default)]
+struct Explicit {
+    start: bool,
+    max_value: bool,
+    slice_duration: bool,
+    clock: bool,
 }
 
 impl Default for Config {
@@ -53,7 +576,13 @@ impl Default for Config {
             max_value: 1_000_000_000,
             slice_duration: 60_000_000_000,
             num_slices: 60,
-            start: time::precise_time_ns(),
+            start: 0,
+            circular: false,
+            boundaries: None,
+            clock: time::precise_time_ns,
+            time_unit: TimeUnit::Nanos,
+            strict: false,
+            explicit: Explicit::default(),
         }
     }
 }
@@ -64,21 +593,34 @@ impl Config {
     /// # Defaults
     /// * precision => 3
     /// * max_memory => 0 (unlimited)
-    /// * max_value => 1_000_000_000 (1 second in nanoseconds)
-    /// * slice_duration => 60_000_000_000 (1 minute in nanoseconds)
+    /// * max_value => 1_000_000_000 (1 second in the `time_unit`)
+    /// * slice_duration => 60_000_000_000 (1 minute in the `time_unit`)
     /// * num_slices => 60 (1 hour of heatmap)
-    /// * start => 0 (start from time 0)
+    /// * start => the time of the `clock` when the `Heatmap` is built
+    /// * circular => false (reject samples after the last `Slice`)
+    /// * boundaries => none (every `Slice` lasts for `slice_duration`)
+    /// * clock => `time::precise_time_ns`, scaled to the `time_unit`
+    /// * time_unit => `TimeUnit::Nanos`
+    /// * strict => false (samples which can not be recorded are not counted)
     pub fn new() -> Config {
         Default::default()
     }
 
-    /// set the number of significant figures to mantain for values
+    /// set the number of significant figures to mantain for values. Values
+    /// below the next power of two above 10^`precision` each have their own
+    /// bucket, and larger values share buckets whose width grows with the
+    /// value. `Histogram` has no fixed-width bucketing, so raising the
+    /// precision is the way to keep small values exact
     pub fn precision(mut self, precision: u32) -> Self {
         self.precision = precision;
         self
     }
 
-    /// set a bound on memory usage of `Heatmap`
+    /// set a bound on memory usage of `Heatmap`. The bound is divided evenly
+    /// between the `Slice`s, rounding down, so the total allocated never
+    /// exceeds it. Every bucket holds a `u64` count, as `Histogram` does not
+    /// support narrower counters, so lowering `precision` or `max_value` is
+    /// the way to fit more slices within the bound
     pub fn max_memory(mut self, bytes: u32) -> Self {
         self.max_memory = bytes;
         self
@@ -87,12 +629,14 @@ impl Config {
     /// set the max value to store within the `Heatmap`
     pub fn max_value(mut self, value: u64) -> Self {
         self.max_value = value;
+        self.explicit.max_value = true;
         self
     }
 
     /// set the duration of each `Slice` within the `Heatmap`
     pub fn slice_duration(mut self, duration: u64) -> Self {
         self.slice_duration = duration;
+        self.explicit.slice_duration = true;
         self
     }
 
@@ -105,436 +649,4528 @@ impl Config {
     /// the start time of the `Heatmap`, used for `Slice` indexing
     pub fn start(mut self, time: u64) -> Self {
         self.start = time;
+        self.explicit.start = true;
         self
     }
 
-    /// creates the `Heatmap` from the `Config`
-    pub fn build(self) -> Option<Heatmap> {
-        Heatmap::configured(self)
+    /// set the start time and derive the `slice_duration` so that the
+    /// `Heatmap` spans from `start` to `stop`. This uses the current value of
+    /// `num_slices`, so it should be set first. The duration is rounded down,
+    /// so if the span is not evenly divisible the `Heatmap` will stop slightly
+    /// before `stop`. If `stop` is not after `start`, `build()` will return
+    /// `ConfigError::SliceDuration`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .num_slices(10)
+    ///     .span(1_000, 11_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.oldest().unwrap().start(), 1_000);
+    /// assert_eq!(h.latest().unwrap().stop(), 11_000);
+    /// ```
+    pub fn span(mut self, start: u64, stop: u64) -> Self {
+        self.start = start;
+        self.explicit.start = true;
+        self.slice_duration = stop.saturating_sub(start) / cmp::max(self.num_slices, 1) as u64;
+        self.explicit.slice_duration = true;
+        self
     }
-}
-
-
-#[derive(Clone, Copy)]
-struct Counters {
-    entries_total: u64,
-}
 
-impl Default for Counters {
-    fn default() -> Counters {
-        Counters { entries_total: 0 }
+    /// when enabled, a sample which is later than the last `Slice` causes the
+    /// oldest `Slice`s to be cleared and reused, advancing the time range of
+    /// the `Heatmap` so that it acts as a rolling window
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .circular(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment(0, 1).unwrap();
+    /// h.increment(9_000, 1).unwrap();
+    /// assert_eq!(h.entries(), 2);
+    ///
+    /// // advances by one slice, recycling the one which held the first sample
+    /// h.increment(10_000, 1).unwrap();
+    /// assert_eq!(h.entries(), 2);
+    /// assert_eq!(h.into_iter().next().unwrap().start(), 1_000);
+    /// ```
+    pub fn circular(mut self, enabled: bool) -> Self {
+        self.circular = enabled;
+        self
     }
-}
 
-impl Counters {
-    pub fn new() -> Counters {
-        Default::default()
+    /// set whether samples which `increment_by` can not record should be
+    /// counted, including those dropped by `merge`, `increment_batch`, and
+    /// other methods built on it. In strict mode the number of such samples
+    /// is available from `Heatmap::dropped`, so that a misconfigured
+    /// `Heatmap` can be noticed
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let config = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0);
+    /// let mut a = config.clone().strict(true).build().unwrap();
+    /// let mut b = config.start(5_000).build().unwrap();
+    ///
+    /// b.increment_by(5_000, 1, 2).unwrap();
+    /// b.increment_by(12_000, 1, 3).unwrap();
+    ///
+    /// // the samples after the end of `a` are dropped
+    /// a.merge(&b);
+    /// assert_eq!(a.entries(), 2);
+    /// assert_eq!(a.dropped(), 3);
+    /// ```
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
     }
 
-    pub fn clear(&mut self) {
-        self.entries_total = 0;
+    /// set explicit boundaries between the `Slice`s, allowing them to have
+    /// different durations. The first boundary is the start of the `Heatmap`
+    /// and the last is its stop, so there is one fewer `Slice` than there are
+    /// boundaries. This takes the place of `start`, `slice_duration`, and
+    /// `num_slices`, and can not be combined with circular mode. Evenly
+    /// spaced boundaries are indexed as quickly as a fixed `slice_duration`,
+    /// otherwise the `Slice` for a time is found with a binary search
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .boundaries(vec![0, 10, 100, 1_000])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment(5, 1).unwrap();
+    /// h.increment(50, 1).unwrap();
+    /// h.increment(500, 1).unwrap();
+    ///
+    /// assert_eq!(h.num_slices(), 3);
+    /// assert_eq!(h.latest().unwrap().start(), 100);
+    /// assert_eq!(h.latest().unwrap().entries(), 1);
+    /// assert!(h.increment(1_000, 1).is_err());
+    /// ```
+    pub fn boundaries(mut self, boundaries: Vec<u64>) -> Self {
+        self.boundaries = Some(boundaries);
+        self
     }
-}
-
-#[derive(Clone)]
-struct Data {
-    data: Vec<Histogram>,
-    counters: Counters,
-    iterator: usize,
-    start: u64,
-    stop: u64,
-}
-
-#[derive(Clone, Copy)]
-struct Properties;
-
-/// main datastructure of `Heatmap`
-#[derive(Clone)]
-pub struct Heatmap {
-    config: Config,
-    data: Data,
-    properties: Properties,
-}
-
-/// a `Histogram` with time boundaries
-#[derive(Clone)]
-pub struct Slice {
-    start: u64,
-    stop: u64,
-    histogram: Histogram,
-}
 
-impl Slice {
-    /// returns the start time of the `Slice`
-    pub fn start(&self) -> u64 {
-        self.start
+    /// get the number of significant figures to maintain for values. The
+    /// getters are prefixed with `get_` as the plain names are the setters
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .precision(2)
+    ///     .max_value(1_000)
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let config = h.config();
+    /// assert_eq!(config.get_precision(), 2);
+    /// assert_eq!(config.get_max_value(), 1_000);
+    /// assert_eq!(config.get_max_memory(), 0);
+    /// assert_eq!(config.get_slice_duration(), 1_000);
+    /// assert_eq!(config.get_num_slices(), 10);
+    /// assert_eq!(config.get_start(), 0);
+    /// assert!(!config.get_circular());
+    /// ```
+    pub fn get_precision(&self) -> u32 {
+        self.precision
     }
 
-    /// returns the stop time of the `Slice`
-    pub fn stop(&self) -> u64 {
-        self.stop
+    /// get the bound on memory usage, where 0 is unlimited
+    pub fn get_max_memory(&self) -> u32 {
+        self.max_memory
     }
 
-    /// returns the `Histogram` for the `Slice`
-    pub fn histogram(self) -> Histogram {
-        self.histogram
+    /// get the max value to store within the `Heatmap`
+    pub fn get_max_value(&self) -> u64 {
+        self.resolved().max_value
     }
-}
-
-/// Iterator over a `Heatmap`'s `Slice`s
-pub struct Iter<'a> {
-    heatmap: &'a Heatmap,
-    index: usize,
-}
 
-impl<'a> Iter<'a> {
-    fn new(heatmap: &'a Heatmap) -> Iter<'a> {
-        Iter {
-            heatmap: heatmap,
-            index: 0,
-        }
+    /// get the duration of each `Slice`
+    pub fn get_slice_duration(&self) -> u64 {
+        self.resolved().slice_duration
     }
-}
 
-impl<'a> Iterator for Iter<'a> {
-    type Item = Slice;
+    /// get the number of `Slice`s to store
+    pub fn get_num_slices(&self) -> usize {
+        self.num_slices
+    }
 
-    fn next(&mut self) -> Option<Slice> {
-        if self.index == (self.heatmap.config.num_slices as usize) {
-            None
-        } else {
-            let start = self.heatmap.data.start +
-                (self.heatmap.config.slice_duration * self.index as u64);
-            let current = self.index;
-            self.index += 1;
-            Some(Slice {
-                start: start,
-                stop: start + self.heatmap.config.slice_duration,
-                histogram: self.heatmap.data.data[current].clone(),
-            })
-        }
+    /// get the configured start time of the `Heatmap`. Unless it was set, this
+    /// is the current time of the `clock`, which is read again when the
+    /// `Heatmap` is built
+    pub fn get_start(&self) -> u64 {
+        self.resolved().start
     }
-}
 
-impl<'a> IntoIterator for &'a Heatmap {
-    type Item = Slice;
-    type IntoIter = Iter<'a>;
+    /// get whether the `Heatmap` acts as a rolling window
+    pub fn get_circular(&self) -> bool {
+        self.circular
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        Iter::new(self)
+    /// get whether samples which can not be recorded are surfaced
+    pub fn get_strict(&self) -> bool {
+        self.strict
     }
-}
 
-impl Default for Heatmap {
-    fn default() -> Heatmap {
-        Heatmap::configured(Config::new()).unwrap()
+    /// get the explicit boundaries between `Slice`s, if they were set. For a
+    /// built `Heatmap` this is `None` when the boundaries were evenly spaced,
+    /// and its `slice_duration` is otherwise the mean, rounded down
+    pub fn get_boundaries(&self) -> Option<&[u64]> {
+        self.boundaries.as_ref().map(|boundaries| &boundaries[..])
     }
-}
 
-impl Heatmap {
-    /// create a new Heatmap with defaults
+    /// set the clock which provides the current time in the `time_unit`. Unless
+    /// the start time is set, the `Heatmap` starts at the current time of the
+    /// clock when it is built. The clock is read by `increment_now`, and used
+    /// to convert an `Instant` into a timestamp
     ///
     /// # Example
     /// ```
     /// # use heatmap::Heatmap;
-    /// let h = Heatmap::new();
-    /// assert_eq!(h.entries(), 0);
+    /// fn clock() -> u64 {
+    ///     42_000
+    /// }
+    ///
+    /// let h = Heatmap::configure()
+    ///     .clock(clock)
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.start_time(), 42_000);
+    ///
+    /// // an explicit start is kept, whichever is set first
+    /// let h = Heatmap::configure().start(0).clock(clock).build().unwrap();
+    /// assert_eq!(h.start_time(), 0);
     /// ```
-    pub fn new() -> Heatmap {
-        Default::default()
+    pub fn clock(mut self, clock: fn() -> u64) -> Self {
+        self.clock = clock;
+        self.explicit.clock = true;
+        self
     }
 
-    /// configure and build a new Heatmap
+    /// set the unit of the timestamps which will be recorded. Unless they are
+    /// set, the `slice_duration` defaults to 1 minute, the `max_value` to 1
+    /// second (or the smallest which the `precision` allows), and the clock to
+    /// one which counts in the unit. These are derived when the `Heatmap` is
+    /// built, so the unit may be set before or after them. The unit is also
+    /// used to convert an `Instant` into a timestamp, and by `rate`
     ///
     /// # Example
     /// ```
-    /// # use heatmap::Heatmap;
-    /// let h = Heatmap::configure()
-    ///     .precision(4) // set precision to 4 digits
-    ///     .max_value(1_000_000_000) // store values up to 1 Million
-    ///     .slice_duration(1_000_000_000) // 1 second slices
-    ///     .num_slices(300) // 300 slices => 5 minutes of records
-    ///     .build() // create the Heatmap
+    /// use heatmap::{Heatmap, TimeUnit};
+    ///
+    /// let mut h = Heatmap::configure()
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .time_unit(TimeUnit::Millis)
+    ///     .build()
     ///     .unwrap();
-    /// assert_eq!(h.entries(), 0);
+    /// assert_eq!(h.config().get_slice_duration(), 60_000);
+    /// assert_eq!(h.config().get_max_value(), 1_000);
+    ///
+    /// h.increment_by(0, 250, 120).unwrap();
+    /// assert_eq!(h.rate(), vec![(0, 2.0), (60_000, 0.0)]);
+    ///
+    /// // explicit settings are kept, whichever is set first
+    /// let config = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .time_unit(TimeUnit::Millis)
+    ///     .start(0);
+    /// assert_eq!(config.build().unwrap().config().get_slice_duration(), 1_000);
     /// ```
-    pub fn configure() -> Config {
-        Config::default()
+    pub fn time_unit(mut self, unit: TimeUnit) -> Self {
+        self.time_unit = unit;
+        self
     }
 
-    // internal function to build a configured `Heatmap`
-    fn configured(config: Config) -> Option<Heatmap> {
-        let mut data = Vec::new();
+    // internal function to copy the `Config` with the settings which were not
+    // set explicitly derived, as they would be by `build`
+    fn resolved(&self) -> Config {
+        let mut config = self.clone();
+        config.resolve_defaults();
+        config
+    }
 
-        for _ in 0..config.num_slices {
-            data.push(
-                Histogram::configure()
-                    .max_value(config.max_value)
-                    .precision(config.precision)
-                    .max_memory(config.max_memory / config.num_slices as u32)
-                    .build()
-                    .unwrap(),
-            );
+    // internal function to derive the settings which were not set explicitly
+    // from the `time_unit` and `clock`
+    fn resolve_defaults(&mut self) {
+        let per_second = self.time_unit.per_second();
+        if !self.explicit.max_value {
+            self.max_value = cmp::max(per_second, 10_u64.saturating_pow(self.precision));
         }
+        if !self.explicit.slice_duration {
+            self.slice_duration = 60 * per_second;
+        }
+        if !self.explicit.clock {
+            self.clock = self.time_unit.clock();
+        }
+        if !self.explicit.start {
+            self.start = (self.clock)();
+        }
+        self.explicit = Explicit {
+            start: true,
+            max_value: true,
+            slice_duration: true,
+            clock: true,
+        };
+    }
 
-        let start = config.start;
-
-        Some(Heatmap {
-            config: config,
-            data: Data {
-                data: data,
-                counters: Counters::new(),
-                iterator: 0,
-                start: start,
-                stop: start + (config.slice_duration * config.num_slices as u64),
-            },
-            properties: Properties,
-        })
+    /// get the unit of the timestamps which will be recorded
+    pub fn get_time_unit(&self) -> TimeUnit {
+        self.time_unit
     }
 
-    /// clear the heatmap data
+    /// creates the `Heatmap` from the `Config`
+    ///
+    /// # Errors
+    /// returns a `ConfigError` describing the first invalid setting
     ///
     /// # Example
     /// ```
-    /// extern crate time;
-    /// extern crate heatmap;
-    ///
-    /// let mut h = heatmap::Heatmap::new();
+    /// use heatmap::{ConfigError, Heatmap};
     ///
-    /// h.increment(time::precise_time_ns(), 1).unwrap();
-    /// assert_eq!(h.entries(), 1);
-    /// h.clear();
-    /// assert_eq!(h.entries(), 0);
+    /// assert_eq!(
+    ///     Heatmap::configure().num_slices(0).build().err(),
+    ///     Some(ConfigError::NumSlices)
+    /// );
+    /// assert_eq!(
+    ///     Heatmap::configure().precision(3).max_value(999).build().err(),
+    ///     Some(ConfigError::MaxValue)
+    /// );
     /// ```
-    pub fn clear(&mut self) {
-        for i in 0..self.config.num_slices {
-            self.data.data[i].clear();
+    pub fn build(self) -> Result<Heatmap, ConfigError> {
+        Heatmap::configured(self)
+    }
+
+    // internal function to build an empty `Histogram` for a single `Slice`
+    fn histogram(&self) -> Option<Histogram> {
+        Histogram::configure()
+            .max_value(self.max_value)
+            .precision(self.precision)
+            .max_memory(self.slice_max_memory())
+            .build()
+    }
+
+    // internal function to validate the boundaries and derive the uniform
+    // geometry from them. Returns the offset of each boundary from the start
+    // if the slices are not all the same duration
+    fn resolve_boundaries(&mut self) -> Result<Option<Vec<u64>>, ConfigError> {
+        let offsets: Vec<u64> = match self.boundaries {
+            None => return Ok(None),
+            Some(ref boundaries) => {
+                if boundaries.len() < 2 ||
+                    self.circular ||
+                    boundaries.windows(2).any(|pair| pair[0] >= pair[1])
+                {
+                    return Err(ConfigError::Boundaries);
+                }
+                boundaries.iter().map(|boundary| boundary - boundaries[0]).collect()
+            }
+        };
+
+        self.start = self.boundaries.as_ref().map_or(0, |boundaries| boundaries[0]);
+        self.num_slices = offsets.len() - 1;
+        self.slice_duration = offsets[self.num_slices] / self.num_slices as u64;
+
+        if offsets.windows(2).all(|pair| pair[1] - pair[0] == self.slice_duration) {
+            self.boundaries = None;
+            Ok(None)
+        } else {
+            Ok(Some(offsets))
         }
+    }
 
-        self.data.counters.clear();
-        self.data.start = time::precise_time_ns();
-        self.data.stop = self.data.start +
-            (self.config.slice_duration * self.config.num_slices as u64);
+    // internal function to find the bytes allocated for the buckets of each
+    // `Histogram`, following the calculation it checks against `max_memory`
+    fn histogram_memory(&self) -> usize {
+        let buckets_inner = 10_u64.pow(self.precision);
+        let linear_power = 64 - buckets_inner.leading_zeros();
+        let max_value_power = 64 - self.max_value.leading_zeros();
+        let buckets_outer = u64::from(max_value_power.saturating_sub(linear_power));
+        let buckets = buckets_inner * buckets_outer + (1 << linear_power);
+        buckets as usize * mem::size_of::<u64>()
     }
 
-    /// increment the count for a value at a time
-    ///
-    /// # Example
-    /// ```
-    /// extern crate heatmap;
-    /// extern crate time;
-    ///
-    /// let mut h = heatmap::Heatmap::new();
-    ///
-    /// h.increment(time::precise_time_ns(), 1).unwrap();
-    /// assert_eq!(h.entries(), 1);
-    /// h.increment(time::precise_time_ns(), 1).unwrap();
-    /// assert_eq!(h.entries(), 2);
-    /// ```
-    pub fn increment(&mut self, time: u64, value: u64) -> Result<(), &'static str> {
-        self.increment_by(time, value, 1_u64)
+    // internal function to find the memory bound for each `Slice`
+    fn slice_max_memory(&self) -> u32 {
+        (u64::from(self.max_memory) / self.num_slices as u64) as u32
     }
+}
 
-    /// increment additional counts for value at a time
-    ///
-    /// # Example
-    /// ```
-    /// extern crate heatmap;
-    /// extern crate time;
-    ///
-    /// let mut h = heatmap::Heatmap::new();
-    ///
-    /// h.increment_by(time::precise_time_ns(), 1, 1).unwrap();
-    /// assert_eq!(h.entries(), 1);
-    ///
-    /// h.increment_by(time::precise_time_ns(), 2, 2).unwrap();
-    /// assert_eq!(h.entries(), 3);
-    ///
-    /// h.increment_by(time::precise_time_ns(), 10, 10).unwrap();
-    /// assert_eq!(h.entries(), 13);
-    /// ```
-    pub fn increment_by(&mut self, time: u64, value: u64, count: u64) -> Result<(), &'static str> {
-        self.data.counters.entries_total = self.data.counters.entries_total.saturating_add(count);
 
-        match self.histogram_index(time) {
-            Ok(histogram_index) => self.data.data[histogram_index].increment_by(value, count),
-            Err(e) => Err(e),
-        }
+#[derive(Clone, Copy, Default)]
+struct Counters {
+    entries_total: u64,
+    // set once `entries_total` has saturated, until it is next cleared
+    overflowed: bool,
+    // samples which could not be recorded, only counted in strict mode
+    dropped: u64,
+}
+
+impl Counters {
+    pub fn new() -> Counters {
+        Default::default()
     }
 
-    /// get the count of items at a quantized time-value point
-    pub fn get(&mut self, time: u64, value: u64) -> Result<u64, &'static str> {
-        match self.histogram_index(time) {
-            Ok(histogram_index) => {
-                match self.data.data[histogram_index].get(value) {
-                    Some(count) => Ok(count),
-                    None => Err("histogram didn't have"),
-                }
+    pub fn clear(&mut self) {
+        self.entries_total = 0;
+        self.overflowed = false;
+        self.dropped = 0;
+    }
+
+    pub fn add(&mut self, count: u64) {
+        match self.entries_total.checked_add(count) {
+            Some(total) => self.entries_total = total,
+            None => {
+                self.entries_total = u64::MAX;
+                self.overflowed = true;
             }
-            Err(e) => Err(e),
         }
     }
+}
 
+#[derive(Clone)]
+struct Data {
+    data: Vec<Histogram>,
+    counters: Counters,
+    start: u64,
+    stop: u64,
+    // a point in time as both an `Instant` and a timestamp, used to convert
+    // between the two
+    anchor: (Instant, u64),
+}
 
+// geometry which is derived from the `Config` when the `Heatmap` is built
+#[derive(Clone)]
+struct Properties {
+    // the number of buckets in each histogram
+    buckets: u64,
+    // the value reported for each bucket
+    values: Vec<u64>,
+    // the smallest value which is counted in each bucket, see `lower_bounds`
+    lower: Vec<u64>,
+    // the offset of each slice boundary from the start, including the stop
+    offsets: Vec<u64>,
+    // whether every slice lasts for `slice_duration`
+    uniform: bool,
+}
 
-    /// internal function to find the index of the histogram in the heatmap
-    fn histogram_index(&mut self, time: u64) -> Result<usize, &'static str> {
-        if time < self.data.start {
-            return Err("sample too early");
-        } else if time >= self.data.stop {
-            return Err("sample too late");
-        }
-        let t = time - self.data.start;
-        let index = (t / self.config.slice_duration) as usize;
-        Ok(index)
+/// main datastructure of `Heatmap`
+///
+/// To share a read-only snapshot with other readers, such as each request to
+/// a metrics endpoint, `clone` the `Heatmap` into an `Arc`. The clone copies
+/// every histogram, so take it once and clone the `Arc` for each reader
+///
+/// # Example
+/// ```
+/// # use heatmap::Heatmap;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let mut h = Heatmap::configure()
+///     .slice_duration(1_000)
+///     .num_slices(10)
+///     .start(0)
+///     .build()
+///     .unwrap();
+/// h.increment(0, 1).unwrap();
+///
+/// let snapshot = Arc::new(h.clone());
+/// h.increment(0, 1).unwrap();
+///
+/// let reader = Arc::clone(&snapshot);
+/// let entries = thread::spawn(move || reader.entries()).join().unwrap();
+/// assert_eq!(entries, 1);
+/// assert_eq!(h.entries(), 2);
+/// ```
+#[derive(Clone)]
+pub struct Heatmap {
+    config: Config,
+    data: Data,
+    properties: Properties,
+}
+
+/// a `Histogram` with time boundaries
+#[derive(Clone)]
+pub struct Slice {
+    start: u64,
+    stop: u64,
+    histogram: Histogram,
+}
+
+impl Slice {
+    /// returns the start time of the `Slice`
+    pub fn start(&self) -> u64 {
+        self.start
     }
 
-    /// return the number of entries in the Histogram
+    /// returns the stop time of the `Slice`
+    pub fn stop(&self) -> u64 {
+        self.stop
+    }
+
+    /// returns the `Histogram` for the `Slice`
+    pub fn histogram(self) -> Histogram {
+        self.histogram
+    }
+
+    /// returns a reference to the `Histogram` for the `Slice`
     ///
     /// # Example
     /// ```
-    /// extern crate heatmap;
-    /// extern crate time;
-    ///
-    /// let mut h = heatmap::Heatmap::new();
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().start(0).build().unwrap();
+    /// h.increment(0, 1).unwrap();
     ///
-    /// assert_eq!(h.entries(), 0);
-    /// h.increment_by(time::precise_time_ns(), 1, 1).unwrap();
-    /// assert_eq!(h.entries(), 1);
+    /// let slice = h.into_iter().next().unwrap();
+    /// assert_eq!(slice.histogram_ref().entries(), 1);
+    /// assert_eq!(slice.start(), 0);
     /// ```
-    pub fn entries(&self) -> u64 {
-        self.data.counters.entries_total
+    pub fn histogram_ref(&self) -> &Histogram {
+        &self.histogram
     }
 
-    /// merge one Heatmap into another Heatmap
+    /// returns the number of entries recorded in the `Slice`
     ///
     /// # Example
     /// ```
-    /// extern crate heatmap;
-    /// extern crate time;
-    ///
-    /// let mut a = heatmap::Heatmap::configure()
-    ///     .num_slices(60)
-    ///     .slice_duration(1_000_000_000)
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
     ///     .build()
     ///     .unwrap();
+    /// h.increment_by(1_000, 1, 3).unwrap();
     ///
-    /// let mut b = heatmap::Heatmap::new();
-    ///
-    /// assert_eq!(a.entries(), 0);
-    /// assert_eq!(b.entries(), 0);
-    ///
-    /// let t0 = time::precise_time_ns();
-    /// let t1 = t0 + 1_000_000_000;
-    ///
-    /// let _ = a.increment(t0, 1);
-    /// let _ = b.increment(t0, 1);
+    /// let entries: Vec<u64> = h.into_iter().map(|s| s.entries()).collect();
+    /// assert_eq!(entries, vec![0, 3]);
+    /// ```
+    pub fn entries(&self) -> u64 {
+        self.histogram.entries()
+    }
+
+    /// returns the minimum value recorded in the `Slice`, if any
     ///
-    /// assert_eq!(a.entries(), 1);
-    /// assert_eq!(b.entries(), 1);
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().num_slices(2).start(0).build().unwrap();
+    /// for value in 1..11 {
+    ///     h.increment(0, value).unwrap();
+    /// }
     ///
-    /// a.merge(&mut b);
+    /// let mut slices = h.into_iter();
+    /// let slice = slices.next().unwrap();
+    /// assert_eq!(slice.minimum(), Some(1));
+    /// assert_eq!(slice.maximum(), Some(10));
+    /// assert_eq!(slice.mean(), Some(6));
     ///
-    /// assert_eq!(a.entries(), 2);
-    /// assert_eq!(a.get(t0, 1).unwrap(), 2);
-    /// assert_eq!(a.get(t0, 2).unwrap(), 0);
-    /// assert_eq!(a.get(t1, 1).unwrap(), 0);
+    /// let empty = slices.next().unwrap();
+    /// assert_eq!(empty.minimum(), None);
     /// ```
-    pub fn merge(&mut self, other: &Heatmap) {
-        for slice in other {
-            let slice = slice.clone();
-            let start = slice.start();
-            for bucket in &slice.histogram {
-                let _ = self.increment_by(start, bucket.value(), bucket.count());
-            }
-        }
+    pub fn minimum(&self) -> Option<u64> {
+        self.histogram.minimum().ok()
     }
 
-    /// save the `Heatmap` to disk. NOTE: format may change in future
-    pub fn save(&self, file: String) {
-        let mut file_handle = File::create(file.clone()).unwrap();
+    /// returns the maximum value recorded in the `Slice`, if any
+    pub fn maximum(&self) -> Option<u64> {
+        self.histogram.maximum().ok()
+    }
 
-        let config = format!(
-            "{} {} {} {:?} {} {:?}\n",
-            self.config.precision,
-            self.config.max_memory,
-            self.config.max_value,
-            self.config.slice_duration,
-            self.config.num_slices,
-            self.config.start
-        ).into_bytes();
-        let _ = file_handle.write_all(&config);
+    /// returns the approximate mean of the values recorded in the `Slice`,
+    /// if any
+    pub fn mean(&self) -> Option<u64> {
+        self.histogram.mean().ok()
+    }
+}
 
-        for slice in self {
-            let histogram = slice.histogram.clone();
-            for bucket in &histogram {
-                if bucket.count() > 0 {
-                    let line = format!("{:?} {} {}\n", slice.start, bucket.value(), bucket.count())
-                        .into_bytes();
-                    let _ = file_handle.write_all(&line);
-                }
-            }
-        }
+// `Histogram` does not implement `Debug`, so show its number of entries
+impl fmt::Debug for Slice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Slice")
+            .field("start", &self.start)
+            .field("stop", &self.stop)
+            .field("entries", &self.entries())
+            .finish()
     }
+}
 
-    /// load the `Heatmap` from file. NOTE: format may change in future
-    pub fn load(file: String) -> Heatmap {
-        let file_handle = File::open(file.clone()).unwrap();
+/// Iterator over a `Heatmap`'s `Slice`s
+pub struct Iter<'a> {
+    heatmap: &'a Heatmap,
+    index: usize,
+}
 
-        let reader = BufReader::new(&file_handle);
+impl<'a> Iter<'a> {
+    fn new(heatmap: &'a Heatmap) -> Iter<'a> {
+        Iter {
+            heatmap,
+            index: 0,
+        }
+    }
+}
 
-        let mut lines = reader.lines();
+impl<'a> Iterator for Iter<'a> {
+    type Item = Slice;
 
-        let config = lines.next().unwrap().unwrap();
-        let config_tokens: Vec<&str> = config.split_whitespace().collect();
+    fn next(&mut self) -> Option<Slice> {
+        if self.index == self.heatmap.config.num_slices {
+            None
+        } else {
+            let current = self.index;
+            self.index += 1;
+            Some(self.heatmap.slice_at(current))
+        }
+    }
+}
 
-        let precision: u32 = config_tokens[0].parse().unwrap();
-        let max_memory: u32 = config_tokens[1].parse().unwrap();
-        let max_value: u64 = config_tokens[2].parse().unwrap();
-        let slice_duration: u64 = config_tokens[3].parse().unwrap();
-        let num_slices: usize = config_tokens[4].parse().unwrap();
-        let start: u64 = config_tokens[5].parse().unwrap();
+/// a borrowed view of a `Slice`, as produced by `Heatmap::slices`
+#[derive(Clone, Copy)]
+pub struct SliceRef<'a> {
+    start: u64,
+    stop: u64,
+    histogram: &'a Histogram,
+}
 
-        let mut heatmap = Heatmap::configure()
-            .precision(precision)
-            .max_memory(max_memory)
-            .max_value(max_value)
-            .slice_duration(slice_duration)
-            .num_slices(num_slices)
-            .start(start)
+impl<'a> SliceRef<'a> {
+    /// returns the start time of the `Slice`
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// returns the stop time of the `Slice`
+    pub fn stop(&self) -> u64 {
+        self.stop
+    }
+
+    /// returns a reference to the `Histogram` for the `Slice`
+    pub fn histogram(&self) -> &'a Histogram {
+        self.histogram
+    }
+
+    /// returns the number of entries recorded in the `Slice`
+    pub fn entries(&self) -> u64 {
+        self.histogram.entries()
+    }
+}
+
+/// Iterator over a `Heatmap`'s `Slice`s which borrows each `Histogram`
+pub struct SliceRefIter<'a> {
+    heatmap: &'a Heatmap,
+    index: usize,
+}
+
+impl<'a> Iterator for SliceRefIter<'a> {
+    type Item = SliceRef<'a>;
+
+    fn next(&mut self) -> Option<SliceRef<'a>> {
+        if self.index == self.heatmap.config.num_slices {
+            None
+        } else {
+            let current = self.index;
+            self.index += 1;
+            Some(SliceRef {
+                start: self.heatmap.slice_start(current),
+                stop: self.heatmap.slice_stop(current),
+                histogram: &self.heatmap.data.data[current],
+            })
+        }
+    }
+}
+
+/// the values at several percentiles for a single `Slice`, as returned by
+/// `Heatmap::percentile_band`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PercentileRow {
+    /// the start time of the `Slice`
+    pub start: u64,
+    /// the value at each percentile, in the order they were requested
+    pub values: Vec<u64>,
+}
+
+/// Colormaps for rendering a `Heatmap` as an image
+#[cfg(feature = "image")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colormap {
+    /// black for empty cells through to white for the busiest
+    Grayscale,
+    /// dark purple for empty cells through blue and green to yellow
+    Viridis,
+}
+
+#[cfg(feature = "image")]
+impl Colormap {
+    // the color for an intensity between 0.0 and 1.0
+    fn color(self, intensity: f64) -> [u8; 3] {
+        match self {
+            Colormap::Grayscale => {
+                let v = (intensity * 255.0).round() as u8;
+                [v, v, v]
+            }
+            Colormap::Viridis => {
+                const STOPS: [[f64; 3]; 5] = [
+                    [68.0, 1.0, 84.0],
+                    [59.0, 82.0, 139.0],
+                    [33.0, 145.0, 140.0],
+                    [94.0, 201.0, 98.0],
+                    [253.0, 231.0, 37.0],
+                ];
+                let position = intensity * (STOPS.len() - 1) as f64;
+                let index = cmp::min(position.floor() as usize, STOPS.len() - 2);
+                let fraction = position - index as f64;
+                let mut color = [0; 3];
+                for (channel, c) in color.iter_mut().enumerate() {
+                    let low = STOPS[index][channel];
+                    let high = STOPS[index + 1][channel];
+                    *c = (low + (high - low) * fraction).round() as u8;
+                }
+                color
+            }
+        }
+    }
+}
+
+/// streams `(time, value, count)` records to a file as they arrive, so that
+/// a capture does not need to fit within the time range of a `Heatmap`. The
+/// records can be replayed with a `HeatmapReader`
+///
+/// # Example
+/// ```
+/// # use heatmap::{Heatmap, HeatmapReader, HeatmapWriter};
+/// let path = std::env::temp_dir().join("heatmap_doc_stream.txt");
+///
+/// let mut writer = HeatmapWriter::create(&path).unwrap();
+/// writer.write(1_000, 10, 1).unwrap();
+/// writer.write(2_000, 20, 3).unwrap();
+/// writer.write(1_000_000, 30, 1).unwrap();
+/// writer.flush().unwrap();
+///
+/// let mut h = Heatmap::configure()
+///     .slice_duration(1_000)
+///     .num_slices(10)
+///     .start(0)
+///     .build()
+///     .unwrap();
+///
+/// HeatmapReader::open(&path).unwrap().replay(&mut h).unwrap();
+/// assert_eq!(h.entries(), 4);
+/// ```
+pub struct HeatmapWriter {
+    writer: BufWriter<File>,
+}
+
+impl HeatmapWriter {
+    /// create the file, replacing any which already exists
+    ///
+    /// # Errors
+    /// returns any IO error encountered while creating the file
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<HeatmapWriter> {
+        Ok(HeatmapWriter { writer: BufWriter::new(File::create(path)?) })
+    }
+
+    /// append a record of `count` samples of a value at a time
+    ///
+    /// # Errors
+    /// returns any IO error encountered while writing the file
+    pub fn write(&mut self, time: u64, value: u64, count: u64) -> io::Result<()> {
+        writeln!(self.writer, "{} {} {}", time, value, count)
+    }
+
+    /// flush any buffered records to the file. Records are also flushed when
+    /// the `HeatmapWriter` is dropped, but any error is then ignored
+    ///
+    /// # Errors
+    /// returns any IO error encountered while writing the file
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// reads the `(time, value, count)` records written by a `HeatmapWriter`,
+/// either lazily as an iterator or by replaying them into a `Heatmap`
+pub struct HeatmapReader {
+    lines: io::Lines<BufReader<File>>,
+    line: usize,
+}
+
+impl HeatmapReader {
+    /// open a file written by a `HeatmapWriter`
+    ///
+    /// # Errors
+    /// returns any IO error encountered while opening the file
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<HeatmapReader> {
+        Ok(HeatmapReader {
+            lines: BufReader::new(File::open(path)?).lines(),
+            line: 0,
+        })
+    }
+
+    /// increment the `Heatmap` with every remaining record. As with `load`,
+    /// records which the `Heatmap` rejects are silently dropped
+    ///
+    /// # Errors
+    /// returns a `LoadError` if the file cannot be read or a record is
+    /// malformed, in which case earlier records have already been recorded
+    pub fn replay(self, heatmap: &mut Heatmap) -> Result<(), LoadError> {
+        for record in self {
+            let (time, value, count) = record?;
+            let _ = heatmap.increment_by(time, value, count);
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for HeatmapReader {
+    type Item = Result<(u64, u64, u64), LoadError>;
+
+    fn next(&mut self) -> Option<Result<(u64, u64, u64), LoadError>> {
+        let line = self.lines.next()?;
+        self.line += 1;
+        Some(match line {
+            Ok(line) => parse_record(&line, self.line),
+            Err(error) => Err(LoadError::Io(error)),
+        })
+    }
+}
+
+// the number of buckets to merge below which `Heatmap::merge_all` stays on
+// the calling thread, even with the `parallel` feature, as spawning threads
+// would cost more than it saves
+const PARALLEL_MERGE_BUCKETS: u64 = 1 << 20;
+
+// the next shard to hand out to a thread recording into a `SyncHeatmap`
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// a `Heatmap` which may be shared between threads and incremented through a
+/// shared reference. Increments are spread across several shards, each an
+/// independent `Heatmap` behind its own lock, with every thread sticking to
+/// one shard so that threads rarely contend. The shards are merged on read
+///
+/// # Example
+/// ```
+/// # use heatmap::{Heatmap, SyncHeatmap};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let config = Heatmap::configure()
+///     .slice_duration(1_000)
+///     .num_slices(10)
+///     .start(0);
+/// let h = Arc::new(SyncHeatmap::new(config).unwrap());
+///
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| {
+///         let h = h.clone();
+///         thread::spawn(move || for time in 0..10_000 {
+///             h.increment(time, 1).unwrap();
+///         })
+///     })
+///     .collect();
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+///
+/// assert_eq!(h.entries(), 40_000);
+/// assert_eq!(h.merged().get(0, 1).unwrap(), 4_000);
+/// ```
+pub struct SyncHeatmap {
+    shards: Vec<Mutex<Heatmap>>,
+}
+
+impl SyncHeatmap {
+    /// create a new `SyncHeatmap` with one shard for each available thread
+    ///
+    /// # Errors
+    /// returns a `ConfigError` if the `Config` is invalid
+    pub fn new(config: Config) -> Result<SyncHeatmap, ConfigError> {
+        let shards = thread::available_parallelism().map_or(1, |n| n.get());
+        SyncHeatmap::with_shards(config, shards)
+    }
+
+    /// create a new `SyncHeatmap` with a fixed number of shards, which is
+    /// raised to one if zero. Each shard uses the full `max_memory`
+    ///
+    /// # Errors
+    /// returns a `ConfigError` if the `Config` is invalid
+    pub fn with_shards(config: Config, shards: usize) -> Result<SyncHeatmap, ConfigError> {
+        let heatmap = config.build()?;
+        let shards = (0..cmp::max(shards, 1))
+            .map(|_| Mutex::new(heatmap.clone()))
+            .collect();
+        Ok(SyncHeatmap { shards })
+    }
+
+    /// increment the count for a value at a time
+    ///
+    /// # Errors
+    /// returns an error if the time is outside of the `Heatmap` or the value
+    /// is larger than `max_value`
+    pub fn increment(&self, time: u64, value: u64) -> Result<(), HeatmapError> {
+        self.increment_by(time, value, 1)
+    }
+
+    /// increment additional counts for a value at a time
+    ///
+    /// # Errors
+    /// returns an error if the time is outside of the `Heatmap` or the value
+    /// is larger than `max_value`
+    pub fn increment_by(&self, time: u64, value: u64, count: u64) -> Result<(), HeatmapError> {
+        let shard = SHARD.with(|shard| *shard) % self.shards.len();
+        self.lock(shard).increment_by(time, value, count)
+    }
+
+    /// returns the number of entries across every shard
+    pub fn entries(&self) -> u64 {
+        (0..self.shards.len()).fold(0_u64, |total, shard| {
+            total.saturating_add(self.lock(shard).entries())
+        })
+    }
+
+    /// merge every shard into a single `Heatmap`. Shards are locked one at a
+    /// time, so increments made while merging may or may not be included.
+    /// Circular shards are first advanced to the latest of their time ranges
+    pub fn merged(&self) -> Heatmap {
+        let mut heatmaps: Vec<Heatmap> =
+            (0..self.shards.len()).map(|shard| self.lock(shard).clone()).collect();
+
+        let stop = heatmaps.iter().map(|h| h.data.stop).max().unwrap_or(0);
+        for heatmap in &mut heatmaps {
+            if heatmap.data.stop < stop {
+                heatmap.advance(stop - 1);
+            }
+        }
+
+        // every shard was built from the same config, so shares its geometry
+        Heatmap::merge_all(&heatmaps).expect("shards share one Config")
+    }
+
+    /// clear the counts of every shard
+    pub fn clear(&self) {
+        for shard in 0..self.shards.len() {
+            self.lock(shard).clear();
+        }
+    }
+
+    // internal function to lock a shard. A panic while holding the lock can
+    // not leave a `Heatmap` in an unusable state, so poisoning is ignored
+    fn lock(&self, shard: usize) -> MutexGuard<'_, Heatmap> {
+        self.shards[shard].lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<'a> IntoIterator for &'a Heatmap {
+    type Item = Slice;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter::new(self)
+    }
+}
+
+impl Default for Heatmap {
+    fn default() -> Heatmap {
+        Heatmap::configured(Config::new()).unwrap()
+    }
+}
+
+// shows the config and number of entries rather than every bucket
+impl fmt::Debug for Heatmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Heatmap")
+            .field("config", &self.config)
+            .field("start", &self.data.start)
+            .field("stop", &self.data.stop)
+            .field("entries", &self.entries())
+            .finish()
+    }
+}
+
+/// two `Heatmap`s are equal if they have the same slices and buckets, cover
+/// the same time range, and have the same count in every bucket. Heatmaps
+/// which only differ in start time are not equal, but can be compared after
+/// aligning them with `rebase`. Settings which do not affect the contents,
+/// such as `circular` and `clock`, are not compared
+///
+/// # Example
+/// ```
+/// # use heatmap::Heatmap;
+/// let mut a = Heatmap::configure().start(0).build().unwrap();
+/// let mut b = Heatmap::configure().start(1_000).build().unwrap();
+/// a.increment(0, 1).unwrap();
+/// b.increment(1_000, 1).unwrap();
+/// assert!(a != b);
+///
+/// b.rebase(0).unwrap();
+/// assert_eq!(a, b);
+/// ```
+impl PartialEq for Heatmap {
+    fn eq(&self, other: &Heatmap) -> bool {
+        self.same_geometry(other) &&
+            self.data.start == other.data.start &&
+            self.data.data.iter().zip(&other.data.data).all(|(a, b)| {
+                a.into_iter().map(|bucket| bucket.count()).eq(
+                    b.into_iter().map(|bucket| bucket.count()),
+                )
+            })
+    }
+}
+
+/// prints a short summary of the geometry and overall percentiles
+///
+/// # Example
+/// ```
+/// # use heatmap::Heatmap;
+/// let mut h = Heatmap::configure()
+///     .slice_duration(1_000)
+///     .num_slices(10)
+///     .start(0)
+///     .build()
+///     .unwrap();
+///
+/// h.increment_by(0, 1, 3).unwrap();
+/// h.increment(5_000, 100).unwrap();
+///
+/// assert_eq!(
+///     h.to_string(),
+///     "10 slices of 1000 from 0 to 10000\nentries: 4 p50: 1 p99: 100"
+/// );
+/// ```
+impl fmt::Display for Heatmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.properties.uniform {
+            write!(f, "{} slices of {}", self.config.num_slices, self.config.slice_duration)?;
+        } else {
+            write!(f, "{} slices of varying duration", self.config.num_slices)?;
+        }
+        let percentiles = histogram_percentiles(&self.summary(), &[50.0, 99.0]);
+        write!(
+            f,
+            " from {} to {}\nentries: {} p50: {} p99: {}",
+            self.data.start,
+            self.data.stop,
+            self.entries(),
+            percentiles[0],
+            percentiles[1]
+        )
+    }
+}
+
+/// merges another `Heatmap` into this one with the same timestamp-based
+/// semantics as `Heatmap::merge`, so samples which fall outside this heatmap's
+/// time range are dropped
+///
+/// # Example
+/// ```
+/// # use heatmap::Heatmap;
+/// let mut total = Heatmap::configure().start(0).build().unwrap();
+///
+/// for shard in 1..4 {
+///     let mut h = Heatmap::configure().start(0).build().unwrap();
+///     h.increment(0, shard).unwrap();
+///     total += &h;
+/// }
+///
+/// assert_eq!(total.entries(), 3);
+/// ```
+impl<'a> AddAssign<&'a Heatmap> for Heatmap {
+    fn add_assign(&mut self, other: &'a Heatmap) {
+        self.merge(other);
+    }
+}
+
+/// produces a new `Heatmap` with the geometry of the left-hand side, merging
+/// in the right-hand side as with `Heatmap::merge`
+///
+/// # Example
+/// ```
+/// # use heatmap::Heatmap;
+/// let mut a = Heatmap::configure().start(0).build().unwrap();
+/// let mut b = Heatmap::configure().start(0).build().unwrap();
+/// a.increment(0, 1).unwrap();
+/// b.increment(0, 2).unwrap();
+///
+/// let c = &a + &b;
+/// assert_eq!(c.entries(), 2);
+/// assert_eq!(a.entries(), 1);
+/// ```
+impl<'a> Add<&'a Heatmap> for &Heatmap {
+    type Output = Heatmap;
+
+    fn add(self, other: &'a Heatmap) -> Heatmap {
+        let mut heatmap = self.clone();
+        heatmap.merge(other);
+        heatmap
+    }
+}
+
+impl Heatmap {
+    /// create a new Heatmap with defaults
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::new();
+    /// assert_eq!(h.entries(), 0);
+    /// ```
+    pub fn new() -> Heatmap {
+        Default::default()
+    }
+
+    /// configure and build a new Heatmap
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .precision(4) // set precision to 4 digits
+    ///     .max_value(1_000_000_000) // store values up to 1 Million
+    ///     .slice_duration(1_000_000_000) // 1 second slices
+    ///     .num_slices(300) // 300 slices => 5 minutes of records
+    ///     .build() // create the Heatmap
+    ///     .unwrap();
+    /// assert_eq!(h.entries(), 0);
+    /// ```
+    pub fn configure() -> Config {
+        Config::default()
+    }
+
+    /// rebuild a `Heatmap` from `Slice`s, such as those produced by iterating
+    /// over another `Heatmap`. Each slice is placed according to its start
+    /// time, and slices which share a position are merged
+    ///
+    /// # Errors
+    /// returns `InvalidConfig` if the `Config` is invalid, `Incompatible`
+    /// unless there is one slice for each of `num_slices` and each has the
+    /// buckets of the `Config`, and
+    /// `SampleTooEarly` or `SampleTooLate` if a slice starts outside the
+    /// `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let config = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0);
+    ///
+    /// let mut a = config.clone().build().unwrap();
+    /// a.increment(5_000, 1).unwrap();
+    ///
+    /// let slices = a.into_iter().collect();
+    /// let mut b = Heatmap::from_slices(config, slices).unwrap();
+    /// assert_eq!(b.entries(), 1);
+    /// assert_eq!(b.get(5_000, 1).unwrap(), 1);
+    /// ```
+    pub fn from_slices(config: Config, slices: Vec<Slice>) -> Result<Heatmap, HeatmapError> {
+        let mut heatmap = config.build()?;
+
+        if slices.len() != heatmap.config.num_slices {
+            return Err(HeatmapError::Incompatible);
+        }
+
+        let values = &heatmap.properties.values;
+        let matching = |slice: &Slice| {
+            slice.histogram.into_iter().map(|bucket| bucket.value()).eq(values.iter().cloned())
+        };
+        if !slices.iter().all(matching) {
+            return Err(HeatmapError::Incompatible);
+        }
+
+        for slice in slices {
+            let index = heatmap.histogram_index(slice.start())?;
+            let lower = &heatmap.properties.lower;
+            merge_histogram(&mut heatmap.data.data[index], &slice.histogram, lower);
+            heatmap.data.counters.add(slice.entries());
+        }
+
+        Ok(heatmap)
+    }
+
+    // internal function to build a configured `Heatmap`
+    fn configured(mut config: Config) -> Result<Heatmap, ConfigError> {
+        config.resolve_defaults();
+        let offsets = config.resolve_boundaries()?;
+        if config.num_slices == 0 {
+            return Err(ConfigError::NumSlices);
+        }
+        if config.slice_duration == 0 {
+            return Err(ConfigError::SliceDuration);
+        }
+        // precision of 0 panics on increment, and the bucket math within
+        // `Histogram` may overflow for precision above 7
+        if config.precision < 1 || config.precision > 7 {
+            return Err(ConfigError::Precision);
+        }
+        if config.max_value < 10_u64.pow(config.precision) {
+            return Err(ConfigError::MaxValue);
+        }
+        // a per-slice bound of 0 would mean unlimited, not a tiny limit
+        if config.max_memory > 0 && config.slice_max_memory() == 0 {
+            return Err(ConfigError::MaxMemory);
+        }
+
+        let uniform = offsets.is_none();
+        let offsets = match offsets {
+            Some(offsets) => offsets,
+            None => (0..config.num_slices as u64 + 1)
+                .map(|index| index.checked_mul(config.slice_duration))
+                .collect::<Option<Vec<u64>>>()
+                .ok_or(ConfigError::TimeSpan)?,
+        };
+        let start = config.start;
+        let stop = start
+            .checked_add(offsets[config.num_slices])
+            .ok_or(ConfigError::TimeSpan)?;
+
+        let mut data = Vec::with_capacity(config.num_slices);
+
+        for _ in 0..config.num_slices {
+            data.push(config.histogram().ok_or(ConfigError::MaxMemory)?);
+        }
+
+        let values: Vec<u64> = data[0].into_iter().map(|bucket| bucket.value()).collect();
+        let probe = config.histogram().ok_or(ConfigError::MaxMemory)?;
+
+        let anchor = (Instant::now(), (config.clock)());
+
+        Ok(Heatmap {
+            config,
+            properties: Properties {
+                buckets: data[0].buckets_total(),
+                lower: lower_bounds(probe, &values),
+                values,
+                offsets,
+                uniform,
+            },
+            data: Data {
+                data,
+                counters: Counters::new(),
+                start,
+                stop,
+                anchor,
+            },
+        })
+    }
+
+    /// clear the heatmap data, returning it to the configured start time
+    ///
+    /// # Example
+    /// ```
+    /// extern crate time;
+    /// extern crate heatmap;
+    ///
+    /// let mut h = heatmap::Heatmap::new();
+    ///
+    /// h.increment(time::precise_time_ns(), 1).unwrap();
+    /// assert_eq!(h.entries(), 1);
+    /// h.clear();
+    /// assert_eq!(h.entries(), 0);
+    /// ```
+    ///
+    /// a `Heatmap` which has been moved by `rebase()` or by wrapping around in
+    /// circular mode is also returned to its original time range
+    ///
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.rebase(1_000_000).unwrap();
+    /// h.clear();
+    /// assert_eq!(h.oldest().unwrap().start(), 0);
+    /// h.increment(0, 1).unwrap();
+    /// ```
+    pub fn clear(&mut self) {
+        self.clear_counts();
+        // the configured time range fitted when the `Heatmap` was built
+        let start = self.config.start;
+        let _ = self.rebase(start);
+    }
+
+    /// clear the counts of every `Slice` and the total number of entries,
+    /// leaving the time range of the `Heatmap` where it is. This suits
+    /// reusing a `Heatmap` to replay captures with the same time range
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.rebase(1_000_000).unwrap();
+    /// h.increment(1_000_000, 1).unwrap();
+    /// h.clear_counts();
+    ///
+    /// assert_eq!(h.entries(), 0);
+    /// assert_eq!(h.start_time(), 1_000_000);
+    /// ```
+    pub fn clear_counts(&mut self) {
+        for histogram in &mut self.data.data {
+            histogram.clear();
+        }
+        self.data.counters.clear();
+    }
+
+    /// clear every `Slice` which overlaps the time window `[start, stop)`,
+    /// removing their counts from the total number of entries
+    ///
+    /// # Errors
+    /// returns `InvalidWindow` if `start` is not before `stop`, and
+    /// `SampleTooEarly` or `SampleTooLate` if the window is entirely outside
+    /// the `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for slice in 0..10 {
+    ///     h.increment(slice * 1_000, 1).unwrap();
+    /// }
+    ///
+    /// h.clear_range(2_000, 4_500).unwrap();
+    /// assert_eq!(h.entries(), 7);
+    /// assert_eq!(h.entries_in_window(2_000, 5_000), 0);
+    /// ```
+    pub fn clear_range(&mut self, start: u64, stop: u64) -> Result<(), HeatmapError> {
+        let range = self.window_indices(start, stop)?;
+        for histogram in &mut self.data.data[range] {
+            self.data.counters.entries_total =
+                self.data.counters.entries_total.saturating_sub(histogram.entries());
+            histogram.clear();
+        }
+        Ok(())
+    }
+
+    /// move the `Heatmap` to a new start time, keeping the contents of each
+    /// `Slice` in place. This is useful for aligning heatmaps which were
+    /// recorded against different time bases
+    ///
+    /// # Errors
+    /// returns `SampleTooLate` if the `Heatmap` would end after `u64::MAX`,
+    /// in which case it is not moved
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::{Heatmap, HeatmapError};
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(1_000_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment(1_002_000, 1).unwrap();
+    /// h.rebase(0).unwrap();
+    ///
+    /// assert_eq!(h.get(2_000, 1).unwrap(), 1);
+    /// assert_eq!(h.oldest().unwrap().start(), 0);
+    /// assert_eq!(h.latest().unwrap().stop(), 10_000);
+    ///
+    /// assert_eq!(h.rebase(u64::MAX - 5_000), Err(HeatmapError::SampleTooLate));
+    /// assert_eq!(h.start_time(), 0);
+    /// ```
+    pub fn rebase(&mut self, new_start: u64) -> Result<(), HeatmapError> {
+        let span = self.data.stop - self.data.start;
+        self.data.stop = new_start.checked_add(span).ok_or(HeatmapError::SampleTooLate)?;
+        self.data.start = new_start;
+        Ok(())
+    }
+
+    /// extend the `Heatmap` with empty slices after the last, each lasting
+    /// `slice_duration`, keeping all of the existing slices. The `max_memory`
+    /// bound, if any, is raised to cover the new slices. If the `Heatmap`
+    /// would then stop after `u64::MAX`, it is left unchanged
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment(0, 1).unwrap();
+    /// assert!(h.increment(10_000, 1).is_err());
+    ///
+    /// h.append(5);
+    /// assert_eq!(h.num_slices(), 15);
+    /// assert_eq!(h.stop_time(), 15_000);
+    /// h.increment(10_000, 1).unwrap();
+    /// assert_eq!(h.entries(), 2);
+    ///
+    /// h.append(usize::MAX);
+    /// assert_eq!(h.num_slices(), 15);
+    /// ```
+    pub fn append(&mut self, additional_slices: usize) {
+        if additional_slices == 0 {
+            return;
+        }
+
+        let duration = self.config.slice_duration;
+        let span = (additional_slices as u64)
+            .checked_mul(duration)
+            .and_then(|added| added.checked_add(self.properties.offsets[self.config.num_slices]));
+        // both the current and the configured start must still fit, so that
+        // `clear` can return to the latter
+        let fits = span.is_some_and(|span| {
+            self.data.start.checked_add(span).is_some() &&
+                self.config.start.checked_add(span).is_some()
+        });
+        if !fits {
+            return;
+        }
+
+        // an emptied copy shares the bucket geometry of the existing slices
+        let mut empty = self.data.data[0].clone();
+        empty.clear();
+        self.data.data.resize(self.config.num_slices + additional_slices, empty);
+
+        if self.config.max_memory > 0 {
+            let added = u64::from(self.config.slice_max_memory()) * additional_slices as u64;
+            self.config.max_memory =
+                cmp::min(u64::from(self.config.max_memory) + added, u64::from(u32::MAX)) as u32;
+        }
+
+        for _ in 0..additional_slices {
+            let last = self.properties.offsets[self.properties.offsets.len() - 1];
+            self.properties.offsets.push(last + duration);
+            if let Some(ref mut boundaries) = self.config.boundaries {
+                let last = boundaries[boundaries.len() - 1];
+                boundaries.push(last + duration);
+            }
+        }
+        self.config.num_slices += additional_slices;
+
+        let span = self.properties.offsets[self.config.num_slices];
+        if !self.properties.uniform {
+            // non-uniform slices keep `slice_duration` as their mean
+            self.config.slice_duration = span / self.config.num_slices as u64;
+        }
+        self.data.stop = self.data.start + span;
+    }
+
+    /// increment the count for a value at a time
+    ///
+    /// # Example
+    /// ```
+    /// extern crate heatmap;
+    /// extern crate time;
+    ///
+    /// let mut h = heatmap::Heatmap::new();
+    ///
+    /// h.increment(time::precise_time_ns(), 1).unwrap();
+    /// assert_eq!(h.entries(), 1);
+    /// h.increment(time::precise_time_ns(), 1).unwrap();
+    /// assert_eq!(h.entries(), 2);
+    /// ```
+    pub fn increment(&mut self, time: u64, value: u64) -> Result<(), HeatmapError> {
+        self.increment_by(time, value, 1_u64)
+    }
+
+    /// increment additional counts for value at a time
+    ///
+    /// # Example
+    /// ```
+    /// extern crate heatmap;
+    /// extern crate time;
+    ///
+    /// let mut h = heatmap::Heatmap::new();
+    ///
+    /// h.increment_by(time::precise_time_ns(), 1, 1).unwrap();
+    /// assert_eq!(h.entries(), 1);
+    ///
+    /// h.increment_by(time::precise_time_ns(), 2, 2).unwrap();
+    /// assert_eq!(h.entries(), 3);
+    ///
+    /// h.increment_by(time::precise_time_ns(), 10, 10).unwrap();
+    /// assert_eq!(h.entries(), 13);
+    /// ```
+    ///
+    /// # Errors
+    /// returns an error if the time is outside of the `Heatmap` or the value
+    /// is larger than `max_value`
+    ///
+    /// ```
+    /// use heatmap::{Heatmap, HeatmapError};
+    ///
+    /// let mut h = Heatmap::configure()
+    ///     .max_value(1_000)
+    ///     .slice_duration(1)
+    ///     .num_slices(10)
+    ///     .start(10)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.increment_by(0, 1, 1), Err(HeatmapError::SampleTooEarly));
+    /// assert_eq!(h.increment_by(20, 1, 1), Err(HeatmapError::SampleTooLate));
+    /// assert_eq!(h.increment_by(10, 1_001, 1), Err(HeatmapError::ValueOutOfRange));
+    /// assert_eq!(h.entries(), 0);
+    /// ```
+    pub fn increment_by(&mut self, time: u64, value: u64, count: u64) -> Result<(), HeatmapError> {
+        if self.config.circular && time >= self.data.stop {
+            self.advance(time);
+        }
+
+        let result = self
+            .histogram_index(time)
+            .and_then(|histogram_index| self.increment_index(histogram_index, value, count));
+        if result.is_err() {
+            self.drop_samples(count);
+        }
+        result
+    }
+
+    /// internal function to count samples which could not be recorded, which
+    /// is only done in strict mode
+    fn drop_samples(&mut self, count: u64) {
+        if self.config.strict {
+            self.data.counters.dropped = self.data.counters.dropped.saturating_add(count);
+        }
+    }
+
+    /// internal function to increment the histogram at an index
+    fn increment_index(&mut self, index: usize, value: u64, count: u64) -> Result<(), HeatmapError> {
+        // the histogram counts rejected values as entries, so never pass them
+        if value > self.config.max_value {
+            return Err(HeatmapError::ValueOutOfRange);
+        }
+
+        match self.data.data[index].increment_by(value, count) {
+            Ok(_) => {
+                self.data.counters.add(count);
+                Ok(())
+            }
+            Err(_) => Err(HeatmapError::ValueOutOfRange),
+        }
+    }
+
+    /// increment the `Heatmap` with a buffer of `(time, value, count)`
+    /// records, such as when replaying a capture. The records are grouped by
+    /// `Slice` before being recorded, so that each histogram is visited once.
+    /// Records which `increment_by` would reject are skipped. In circular
+    /// mode the records are instead recorded in order, as each may advance
+    /// the `Heatmap`
+    ///
+    /// # Errors
+    /// this does not currently fail, as rejected records are only left out of
+    /// the returned number of records which were recorded
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .max_value(1_000)
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let records = [(5_000, 1, 1), (0, 1, 2), (5_000, 1, 3), (50_000, 1, 1), (0, 2_000, 1)];
+    /// assert_eq!(h.increment_batch(&records).unwrap(), 3);
+    /// assert_eq!(h.entries(), 6);
+    /// assert_eq!(h.get(5_000, 1).unwrap(), 4);
+    /// ```
+    pub fn increment_batch(&mut self, records: &[(u64, u64, u64)]) -> Result<usize, HeatmapError> {
+        let mut recorded = 0;
+
+        if self.config.circular {
+            for &(time, value, count) in records {
+                if self.increment_by(time, value, count).is_ok() {
+                    recorded += 1;
+                }
+            }
+            return Ok(recorded);
+        }
+
+        let mut indexed: Vec<(usize, u64, u64)> = Vec::with_capacity(records.len());
+        for &(time, value, count) in records {
+            match self.histogram_index(time) {
+                Ok(index) => indexed.push((index, value, count)),
+                Err(_) => self.drop_samples(count),
+            }
+        }
+        indexed.sort_by_key(|&(index, _, _)| index);
+
+        for (index, value, count) in indexed {
+            if self.increment_index(index, value, count).is_ok() {
+                recorded += 1;
+            } else {
+                self.drop_samples(count);
+            }
+        }
+        Ok(recorded)
+    }
+
+    /// increment the `Heatmap` with a buffer of `(time, value, count)`
+    /// records which are already sorted by time, such as those from a
+    /// monotonic capture. Rather than computing the `Slice` for every record,
+    /// a cursor is moved forward as the time crosses each slice boundary.
+    /// Records which `increment_by` would reject are skipped, and sorting is
+    /// only checked in debug builds
+    ///
+    /// # Errors
+    /// this does not currently fail, as rejected records are only left out of
+    /// the returned number of records which were recorded
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(1_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let records = [(0, 1, 1), (1_000, 1, 1), (1_500, 1, 2), (9_000, 1, 3), (20_000, 1, 1)];
+    /// assert_eq!(h.increment_batch_sorted(&records).unwrap(), 3);
+    /// assert_eq!(h.entries(), 6);
+    /// assert_eq!(h.get(1_000, 1).unwrap(), 3);
+    /// ```
+    pub fn increment_batch_sorted(&mut self, records: &[(u64, u64, u64)]) -> Result<usize, HeatmapError> {
+        debug_assert!(
+            records.windows(2).all(|pair| pair[0].0 <= pair[1].0),
+            "records must be sorted by time"
+        );
+
+        let mut recorded = 0;
+        let mut index = 0;
+        for (position, &(time, value, count)) in records.iter().enumerate() {
+            if time < self.data.start {
+                self.drop_samples(count);
+                continue;
+            }
+            if time >= self.data.stop {
+                if !self.config.circular {
+                    // every remaining record is also too late
+                    let remaining = records[position..]
+                        .iter()
+                        .fold(0, |total: u64, record| total.saturating_add(record.2));
+                    self.drop_samples(remaining);
+                    break;
+                }
+                // advancing moves every slice, so restart the cursor
+                self.advance(time);
+                index = 0;
+                if time >= self.data.stop {
+                    self.drop_samples(count);
+                    continue;
+                }
+            }
+            while time >= self.slice_stop(index) {
+                index += 1;
+            }
+            if self.increment_index(index, value, count).is_ok() {
+                recorded += 1;
+            } else {
+                self.drop_samples(count);
+            }
+        }
+        Ok(recorded)
+    }
+
+    /// increment the count for a value at the current time of the clock
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::new();
+    ///
+    /// h.increment_now(1).unwrap();
+    /// assert_eq!(h.entries(), 1);
+    /// ```
+    pub fn increment_now(&mut self, value: u64) -> Result<(), HeatmapError> {
+        let time = (self.config.clock)();
+        self.increment(time, value)
+    }
+
+    /// increment the count for a value at an `Instant`, which is converted to
+    /// a timestamp in the `time_unit`, relative to when the `Heatmap` was
+    /// built. This avoids handling raw timestamps, which remain available
+    /// through `increment` for performance-sensitive callers
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// use std::time::Instant;
+    ///
+    /// let mut h = Heatmap::new();
+    ///
+    /// h.increment_at(Instant::now(), 1).unwrap();
+    /// assert_eq!(h.entries(), 1);
+    /// ```
+    pub fn increment_at(&mut self, instant: Instant, value: u64) -> Result<(), HeatmapError> {
+        let time = self.instant_time(instant);
+        self.increment(time, value)
+    }
+
+    /// internal function to convert an `Instant` into a timestamp
+    fn instant_time(&self, instant: Instant) -> u64 {
+        let (anchor, time) = self.data.anchor;
+        let unit = u128::from(self.config.time_unit.nanos());
+        if instant >= anchor {
+            let elapsed = (instant - anchor).as_nanos() / unit;
+            time.saturating_add(cmp::min(elapsed, u128::from(u64::MAX)) as u64)
+        } else {
+            let earlier = (anchor - instant).as_nanos() / unit;
+            time.saturating_sub(cmp::min(earlier, u128::from(u64::MAX)) as u64)
+        }
+    }
+
+    /// increment the count for a value at a time, recording samples which are
+    /// too early in the first `Slice` and samples which are too late in the
+    /// last `Slice` rather than rejecting them. In circular mode, late samples
+    /// advance the `Heatmap` as they would for `increment`
+    ///
+    /// # Errors
+    /// returns `ValueOutOfRange` if the value is above the `max_value`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(5_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_clamped(0, 1).unwrap();
+    /// h.increment_clamped(20_000, 1).unwrap();
+    ///
+    /// assert_eq!(h.entries(), 2);
+    /// assert_eq!(h.oldest().unwrap().entries(), 1);
+    /// assert_eq!(h.latest().unwrap().entries(), 1);
+    /// ```
+    pub fn increment_clamped(&mut self, time: u64, value: u64) -> Result<(), HeatmapError> {
+        let time = if time < self.data.start {
+            self.data.start
+        } else if !self.config.circular && time >= self.data.stop {
+            self.data.stop - 1
+        } else {
+            time
+        };
+        self.increment(time, value)
+    }
+
+    /// get the count of items at a quantized time-value point
+    ///
+    /// # Errors
+    /// returns an error if the time is outside of the `Heatmap` or the value
+    /// is larger than `max_value`
+    ///
+    /// # Example
+    /// ```
+    /// use heatmap::{Heatmap, HeatmapError};
+    ///
+    /// let mut h = Heatmap::configure()
+    ///     .max_value(1_000)
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// h.increment_by(0, 1, 2).unwrap();
+    ///
+    /// // reading only needs a shared reference
+    /// let h = &h;
+    /// assert_eq!(h.get(0, 1), Ok(2));
+    /// assert_eq!(h.get(0, 2), Ok(0));
+    /// assert_eq!(h.get(10_000, 1), Err(HeatmapError::SampleTooLate));
+    /// assert_eq!(h.get(0, 1_001), Err(HeatmapError::ValueOutOfRange));
+    /// ```
+    pub fn get(&self, time: u64, value: u64) -> Result<u64, HeatmapError> {
+        let histogram_index = self.histogram_index(time)?;
+
+        if value > self.config.max_value {
+            return Err(HeatmapError::ValueOutOfRange);
+        }
+
+        match self.data.data[histogram_index].get(value) {
+            Some(count) => Ok(count),
+            None => Err(HeatmapError::ValueOutOfRange),
+        }
+    }
+
+    /// get the count of items at a time with values between `low` and `high`
+    /// inclusive. Each bound is quantized to the bucket it would be counted
+    /// in, and every bucket from the one holding `low` to the one holding
+    /// `high` is summed. A `high` above the `max_value` is clamped to it
+    ///
+    /// # Errors
+    /// returns an error if the time is outside of the `Heatmap` or `low` is
+    /// larger than `max_value`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .max_value(1_000_000)
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(0, 10, 1).unwrap();
+    /// h.increment_by(0, 500, 2).unwrap();
+    /// h.increment_by(0, 900_000, 3).unwrap();
+    ///
+    /// assert_eq!(h.get_range(0, 10, 500).unwrap(), 3);
+    /// assert_eq!(h.get_range(0, 11, 499).unwrap(), 0);
+    /// assert_eq!(h.get_range(0, 0, u64::MAX).unwrap(), 6);
+    /// assert_eq!(h.get_range(0, 500, 10).unwrap(), 0);
+    /// ```
+    pub fn get_range(&self, time: u64, low: u64, high: u64) -> Result<u64, HeatmapError> {
+        let histogram = self.histogram_at(time)?;
+        if low > self.config.max_value {
+            return Err(HeatmapError::ValueOutOfRange);
+        }
+        if low > high {
+            return Ok(0);
+        }
+
+        let high = cmp::min(high, self.config.max_value);
+        match (self.bucket_index(low), self.bucket_index(high)) {
+            (Some(first), Some(last)) => Ok(histogram
+                .into_iter()
+                .skip(first)
+                .take(last + 1 - first)
+                .map(|bucket| bucket.count())
+                .sum()),
+            _ => Err(HeatmapError::ValueOutOfRange),
+        }
+    }
+
+    /// internal function to find the index of the bucket a value is counted
+    /// in, which is private to `Histogram`, from the lower bound of each
+    fn bucket_index(&self, value: u64) -> Option<usize> {
+        if value > self.config.max_value {
+            return None;
+        }
+        Some(self.properties.lower.partition_point(|&bound| bound <= value) - 1)
+    }
+
+    /// internal function to find the range of slice indices which overlap
+    /// the time window `[start, stop)`
+    fn window_indices(&self, start: u64, stop: u64) -> Result<Range<usize>, HeatmapError> {
+        if start >= stop {
+            return Err(HeatmapError::InvalidWindow);
+        } else if stop <= self.data.start {
+            return Err(HeatmapError::SampleTooEarly);
+        } else if start >= self.data.stop {
+            return Err(HeatmapError::SampleTooLate);
+        }
+        let first = self.histogram_index(cmp::max(start, self.data.start))?;
+        // the number of slices which start before the end of the window
+        let end = cmp::min(stop, self.data.stop) - self.data.start;
+        let last = if self.properties.uniform {
+            end.div_ceil(self.config.slice_duration) as usize
+        } else {
+            self.properties.offsets.partition_point(|&offset| offset < end)
+        };
+        Ok(first..last)
+    }
+
+    /// internal function to recycle the oldest slices until the time is
+    /// within the heatmap. At most `num_slices` are cleared, no matter how far
+    /// the time is beyond the end of the heatmap. The heatmap never moves past
+    /// `u64::MAX`, so a time near it may still be too late afterwards
+    fn advance(&mut self, time: u64) {
+        let slices = cmp::min(
+            (time - self.data.stop) / self.config.slice_duration + 1,
+            (u64::MAX - self.data.stop) / self.config.slice_duration,
+        );
+        let recycled = cmp::min(slices, self.config.num_slices as u64) as usize;
+
+        for histogram in &mut self.data.data[0..recycled] {
+            self.data.counters.entries_total =
+                self.data.counters.entries_total.saturating_sub(histogram.entries());
+            histogram.clear();
+        }
+        self.data.data.rotate_left(recycled);
+
+        let shift = slices * self.config.slice_duration;
+        self.data.start += shift;
+        self.data.stop += shift;
+    }
+
+    /// internal function to clone the slice at an index
+    fn slice_at(&self, index: usize) -> Slice {
+        Slice {
+            start: self.slice_start(index),
+            stop: self.slice_stop(index),
+            histogram: self.data.data[index].clone(),
+        }
+    }
+
+    /// internal function to find the start time of the histogram at an index
+    fn slice_start(&self, index: usize) -> u64 {
+        self.data.start + self.properties.offsets[index]
+    }
+
+    /// internal function to find the stop time of the histogram at an index
+    fn slice_stop(&self, index: usize) -> u64 {
+        self.slice_start(index + 1)
+    }
+
+    /// internal function to find the index of the histogram in the heatmap
+    fn histogram_index(&self, time: u64) -> Result<usize, HeatmapError> {
+        if time < self.data.start {
+            return Err(HeatmapError::SampleTooEarly);
+        } else if time >= self.data.stop {
+            return Err(HeatmapError::SampleTooLate);
+        }
+        let t = time - self.data.start;
+        let index = if self.properties.uniform {
+            (t / self.config.slice_duration) as usize
+        } else {
+            // the last boundary at or before the time, of which there is at
+            // least one as the first offset is always 0
+            self.properties.offsets.partition_point(|&offset| offset <= t) - 1
+        };
+        Ok(index)
+    }
+
+    /// returns true if the time falls within the current time range of the
+    /// `Heatmap`, from its start up to but not including its stop
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(5_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(!h.contains(4_999));
+    /// assert!(h.contains(5_000));
+    /// assert!(h.contains(14_999));
+    /// assert!(!h.contains(15_000));
+    /// ```
+    pub fn contains(&self, time: u64) -> bool {
+        time >= self.data.start && time < self.data.stop
+    }
+
+    /// return the number of entries in the Histogram
+    ///
+    /// # Example
+    /// ```
+    /// extern crate heatmap;
+    /// extern crate time;
+    ///
+    /// let mut h = heatmap::Heatmap::new();
+    ///
+    /// assert_eq!(h.entries(), 0);
+    /// h.increment_by(time::precise_time_ns(), 1, 1).unwrap();
+    /// assert_eq!(h.entries(), 1);
+    /// ```
+    pub fn entries(&self) -> u64 {
+        self.data.counters.entries_total
+    }
+
+    /// returns true if the number of entries has ever saturated at
+    /// `u64::MAX`, since it was last cleared, in which case `entries()` is no
+    /// longer accurate. Clearing or recounting the `Heatmap` resets it
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().start(0).build().unwrap();
+    ///
+    /// h.increment_by(0, 1, u64::MAX).unwrap();
+    /// assert!(!h.overflowed());
+    ///
+    /// h.increment_by(0, 2, 1).unwrap();
+    /// assert!(h.overflowed());
+    /// assert_eq!(h.entries(), u64::MAX);
+    ///
+    /// h.clear_counts();
+    /// assert!(!h.overflowed());
+    /// ```
+    pub fn overflowed(&self) -> bool {
+        self.data.counters.overflowed
+    }
+
+    /// returns the number of samples which `increment_by` could not record
+    /// since the `Heatmap` was last cleared, including those dropped by
+    /// `merge`. This is only counted in strict mode, and is otherwise 0
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .strict(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(0, 1, 3).unwrap();
+    /// assert_eq!(h.dropped(), 0);
+    ///
+    /// assert!(h.increment_by(10_000, 1, 2).is_err());
+    /// assert!(h.increment_by(0, u64::MAX, 1).is_err());
+    /// assert_eq!(h.dropped(), 3);
+    /// ```
+    pub fn dropped(&self) -> u64 {
+        self.data.counters.dropped
+    }
+
+    /// returns true if no entries have been recorded in the `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().start(0).build().unwrap();
+    /// assert!(h.is_empty());
+    ///
+    /// h.increment(0, 1).unwrap();
+    /// assert!(!h.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.entries() == 0
+    }
+
+    /// rebuild the count of entries from the buckets of every `Slice`, which
+    /// restores `entries()` after editing a `Histogram` directly
+    pub fn recount(&mut self) {
+        let mut counters = Counters::new();
+        for histogram in &self.data.data {
+            for bucket in histogram {
+                counters.add(bucket.count());
+            }
+        }
+        counters.dropped = self.data.counters.dropped;
+        self.data.counters = counters;
+    }
+
+    /// returns the sum of the counts of every bucket in every `Slice`. This
+    /// scans all the buckets, unlike `entries()`, so it remains accurate after
+    /// editing a `Histogram` directly
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().start(0).build().unwrap();
+    /// h.increment_by(0, 1, 3).unwrap();
+    /// assert_eq!(h.total_count(), 3);
+    ///
+    /// h.slice_histogram_mut(0).unwrap().increment(2).unwrap();
+    /// assert_eq!(h.total_count(), 4);
+    /// assert_eq!(h.entries(), 3);
+    /// ```
+    pub fn total_count(&self) -> u64 {
+        self.data
+            .data
+            .iter()
+            .flat_map(|histogram| histogram.into_iter().map(|bucket| bucket.count()))
+            .fold(0, u64::saturating_add)
+    }
+
+    /// returns an iterator over the `(slice_start, value, count)` of every
+    /// bucket with a non-zero count, in chronological order and then by
+    /// value. The value is the smallest which the bucket counts, so that
+    /// recording it again counts it in the same bucket. These are the records
+    /// written by `save`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(2_500, 20, 2).unwrap();
+    /// h.increment_by(0, 10, 1).unwrap();
+    /// h.increment_by(0, 5, 3).unwrap();
+    ///
+    /// let cells: Vec<(u64, u64, u64)> = h.cells().collect();
+    /// assert_eq!(cells, vec![(0, 5, 3), (0, 10, 1), (2_000, 20, 2)]);
+    /// ```
+    pub fn cells(&self) -> impl Iterator<Item = (u64, u64, u64)> + '_ {
+        self.data.data.iter().enumerate().flat_map(move |(index, histogram)| {
+            let start = self.slice_start(index);
+            occupied_buckets(histogram, &self.properties.lower)
+                .into_iter()
+                .map(move |(value, count)| (start, value, count))
+        })
+    }
+
+    /// returns an iterator over the `Slice`s of the `Heatmap` which borrows
+    /// each `Histogram` instead of cloning it
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// h.increment(1_000, 5).unwrap();
+    ///
+    /// let p50: Vec<u64> = h.slices()
+    ///     .map(|s| s.histogram().percentile(50.0).unwrap_or(0))
+    ///     .collect();
+    /// assert_eq!(p50, vec![0, 5]);
+    /// ```
+    pub fn slices(&self) -> SliceRefIter<'_> {
+        SliceRefIter {
+            heatmap: self,
+            index: 0,
+        }
+    }
+
+    /// returns an iterator over only the `Slice`s which have entries, so that
+    /// sparse heatmaps only clone the histograms which are populated
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(60)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// h.increment(5_000, 1).unwrap();
+    /// h.increment(42_000, 1).unwrap();
+    ///
+    /// let starts: Vec<u64> = h.non_empty_slices().map(|s| s.start()).collect();
+    /// assert_eq!(starts, vec![5_000, 42_000]);
+    /// ```
+    pub fn non_empty_slices(&self) -> impl Iterator<Item = Slice> + '_ {
+        (0..self.config.num_slices)
+            .filter(move |&index| self.data.data[index].entries() > 0)
+            .map(move |index| self.slice_at(index))
+    }
+
+    /// returns an iterator over the start time and `Histogram` of each
+    /// `Slice`, for when the rest of the `Slice` is not needed
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// h.increment(1_000, 5).unwrap();
+    ///
+    /// let entries: Vec<(u64, u64)> = h.iter_histograms()
+    ///     .map(|(start, histogram)| (start, histogram.entries()))
+    ///     .collect();
+    /// assert_eq!(entries, vec![(0, 0), (1_000, 1)]);
+    /// ```
+    pub fn iter_histograms(&self) -> impl Iterator<Item = (u64, &Histogram)> + '_ {
+        self.data
+            .data
+            .iter()
+            .enumerate()
+            .map(move |(index, histogram)| (self.slice_start(index), histogram))
+    }
+
+    /// returns the `Histogram` of each `Slice` in chronological order
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// h.increment(1_000, 5).unwrap();
+    ///
+    /// assert_eq!(h.histograms().len(), 2);
+    /// assert_eq!(h.histograms()[1].entries(), 1);
+    /// ```
+    pub fn histograms(&self) -> &[Histogram] {
+        &self.data.data
+    }
+
+    /// returns the index of the `Slice` which contains a time, counting from
+    /// the oldest. This matches the order of `histograms()` and iteration, so
+    /// may be used to keep other per-slice data in line with the `Heatmap`
+    ///
+    /// # Errors
+    /// returns `SampleTooEarly` or `SampleTooLate` if the time is outside of
+    /// the `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// use heatmap::{Heatmap, HeatmapError};
+    ///
+    /// let h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(5_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.slice_index(5_000), Ok(0));
+    /// assert_eq!(h.slice_index(7_999), Ok(2));
+    /// assert_eq!(h.slice_index(0), Err(HeatmapError::SampleTooEarly));
+    /// ```
+    pub fn slice_index(&self, time: u64) -> Result<usize, HeatmapError> {
+        self.histogram_index(time)
+    }
+
+    /// returns a reference to the `Histogram` of the `Slice` which contains a
+    /// time, without cloning it
+    ///
+    /// # Errors
+    /// returns `SampleTooEarly` or `SampleTooLate` if the time is outside of
+    /// the `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// use heatmap::{Heatmap, HeatmapError};
+    ///
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// h.increment_by(2_500, 7, 3).unwrap();
+    ///
+    /// let histogram = h.histogram_at(2_000).unwrap();
+    /// assert_eq!(histogram.entries(), 3);
+    /// assert_eq!(histogram.get(7), Some(3));
+    /// assert_eq!(h.histogram_at(10_000).err(), Some(HeatmapError::SampleTooLate));
+    /// ```
+    pub fn histogram_at(&self, time: u64) -> Result<&Histogram, HeatmapError> {
+        let index = self.histogram_index(time)?;
+        Ok(&self.data.data[index])
+    }
+
+    /// returns a mutable reference to the `Histogram` of the `Slice` at an
+    /// index, or `None` if the index is out of range. Changes made through it
+    /// bypass the `Heatmap`, so `entries()` is not updated to match until
+    /// `recount()` is called
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.slice_histogram_mut(1).unwrap().increment_by(5, 3).unwrap();
+    /// assert_eq!(h.get(1_000, 5).unwrap(), 3);
+    /// assert_eq!(h.entries(), 0);
+    /// assert!(h.slice_histogram_mut(2).is_none());
+    ///
+    /// h.recount();
+    /// assert_eq!(h.entries(), 3);
+    /// ```
+    pub fn slice_histogram_mut(&mut self, index: usize) -> Option<&mut Histogram> {
+        self.data.data.get_mut(index)
+    }
+
+    /// consumes the `Heatmap`, returning the `Histogram` of each `Slice` in
+    /// chronological order without cloning them
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// h.increment(1_000, 5).unwrap();
+    ///
+    /// let histograms = h.into_histograms();
+    /// assert_eq!(histograms[0].entries(), 0);
+    /// assert_eq!(histograms[1].entries(), 1);
+    /// ```
+    pub fn into_histograms(self) -> Vec<Histogram> {
+        self.data.data
+    }
+
+    /// returns the values at each of the given percentiles for each `Slice`,
+    /// as a vector of `(slice_start, values)` pairs in chronological order.
+    /// The values are in the same order as the requested percentiles, and
+    /// each `Slice` is traversed once no matter how many are requested.
+    /// Slices without any entries report values of 0
+    ///
+    /// # Errors
+    /// returns `InvalidPercentile` if any percentile is outside 0.0 to 100.0
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for value in 1..101 {
+    ///     h.increment(0, value).unwrap();
+    /// }
+    ///
+    /// assert_eq!(
+    ///     h.percentiles(&[50.0, 90.0, 99.0]).unwrap(),
+    ///     vec![(0, vec![51, 91, 100]), (1_000, vec![0, 0, 0])]
+    /// );
+    /// ```
+    pub fn percentiles(&self, percentiles: &[f64]) -> Result<Vec<(u64, Vec<u64>)>, HeatmapError> {
+        if percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            return Err(HeatmapError::InvalidPercentile);
+        }
+
+        Ok(
+            self.slices()
+                .map(|slice| {
+                    (slice.start(), histogram_percentiles(slice.histogram(), percentiles))
+                })
+                .collect(),
+        )
+    }
+
+    /// returns the values at each of the given percentiles for each `Slice`,
+    /// as with `percentiles`, but as a `PercentileRow` for each `Slice` in
+    /// chronological order. This suits drawing bands between percentiles
+    ///
+    /// # Errors
+    /// returns `InvalidPercentile` if any percentile is outside 0.0 to 100.0
+    ///
+    /// # Example
+    /// ```
+    /// use heatmap::{Heatmap, PercentileRow};
+    ///
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for value in 1..101 {
+    ///     h.increment(1_000, value).unwrap();
+    /// }
+    ///
+    /// let band = h.percentile_band(&[25.0, 75.0]).unwrap();
+    /// assert_eq!(band[0], PercentileRow { start: 0, values: vec![0, 0] });
+    /// assert_eq!(band[1].start, 1_000);
+    /// assert_eq!(band[1].values, vec![25, 76]);
+    /// ```
+    pub fn percentile_band(&self, percentiles: &[f64]) -> Result<Vec<PercentileRow>, HeatmapError> {
+        Ok(
+            self.percentiles(percentiles)?
+                .into_iter()
+                .map(|(start, values)| PercentileRow { start, values })
+                .collect(),
+        )
+    }
+
+    /// returns the mean of the values between two percentiles for each
+    /// `Slice`, as a vector of `(slice_start, mean)` pairs in chronological
+    /// order. Buckets which straddle a percentile contribute in proportion to
+    /// their overlap. Slices without any entries report a value of 0
+    ///
+    /// # Errors
+    /// returns `InvalidPercentile` unless `0.0 <= low < high <= 100.0`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for value in 1..101 {
+    ///     h.increment(0, value).unwrap();
+    /// }
+    /// // outliers which the trimmed mean excludes
+    /// h.increment_by(0, 1_000_000, 5).unwrap();
+    ///
+    /// assert_eq!(h.trimmed_mean(10.0, 90.0).unwrap(), vec![(0, 53), (1_000, 0)]);
+    /// assert!(h.trimmed_mean(90.0, 10.0).is_err());
+    /// ```
+    pub fn trimmed_mean(&self, low: f64, high: f64) -> Result<Vec<(u64, u64)>, HeatmapError> {
+        if !(0.0 <= low && low < high && high <= 100.0) {
+            return Err(HeatmapError::InvalidPercentile);
+        }
+
+        Ok(
+            self.slices()
+                .map(|slice| {
+                    let total = slice.entries() as f64;
+                    let lower = total * low / 100.0;
+                    let upper = total * high / 100.0;
+
+                    let mut seen = 0.0;
+                    let mut sum = 0.0;
+                    for bucket in slice.histogram() {
+                        let count = bucket.count() as f64;
+                        let overlap = upper.min(seen + count) - lower.max(seen);
+                        if overlap > 0.0 {
+                            sum += overlap * bucket.value() as f64;
+                        }
+                        seen += count;
+                        if seen >= upper {
+                            break;
+                        }
+                    }
+
+                    let mean = if upper > lower {
+                        (sum / (upper - lower)).round() as u64
+                    } else {
+                        0
+                    };
+                    (slice.start(), mean)
+                })
+                .collect(),
+        )
+    }
+
+    /// returns the standard deviation of the values in each `Slice`, as a
+    /// vector of `(slice_start, stddev)` pairs in chronological order. This is
+    /// the population standard deviation, calculated from the value and count
+    /// of each bucket. Slices with fewer than two entries report `None`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment(0, 10).unwrap();
+    /// h.increment(0, 20).unwrap();
+    /// h.increment(1_000, 10).unwrap();
+    ///
+    /// assert_eq!(h.stddev(), vec![(0, Some(5.0)), (1_000, None)]);
+    /// ```
+    pub fn stddev(&self) -> Vec<(u64, Option<f64>)> {
+        self.slices()
+            .map(|slice| {
+                let histogram = slice.histogram();
+                let total = histogram.into_iter().map(|bucket| bucket.count()).sum::<u64>();
+                if total < 2 {
+                    return (slice.start(), None);
+                }
+                let mean = histogram
+                    .into_iter()
+                    .map(|bucket| bucket.value() as f64 * bucket.count() as f64)
+                    .sum::<f64>() / total as f64;
+                let variance = histogram
+                    .into_iter()
+                    .map(|bucket| (bucket.value() as f64 - mean).powi(2) * bucket.count() as f64)
+                    .sum::<f64>() / total as f64;
+                (slice.start(), Some(variance.sqrt()))
+            })
+            .collect()
+    }
+
+    /// returns the `(slice_start, value, count)` of the bucket with the
+    /// largest count across the entire `Heatmap`, or `None` if it is empty.
+    /// Ties resolve to the earliest slice and then the lowest value
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.highest_count(), None);
+    ///
+    /// h.increment_by(1_000, 10, 5).unwrap();
+    /// h.increment_by(3_000, 20, 7).unwrap();
+    /// h.increment_by(4_000, 30, 7).unwrap();
+    ///
+    /// assert_eq!(h.highest_count(), Some((3_000, 20, 7)));
+    /// ```
+    pub fn highest_count(&self) -> Option<(u64, u64, u64)> {
+        let mut highest = None;
+        let mut max = 0;
+        for slice in self.slices() {
+            for bucket in slice.histogram() {
+                if bucket.count() > max {
+                    max = bucket.count();
+                    highest = Some((slice.start(), bucket.value(), bucket.count()));
+                }
+            }
+        }
+        highest
+    }
+
+    /// returns the value of the bucket with the largest count for each
+    /// `Slice`, as a vector of `(slice_start, value)` pairs in chronological
+    /// order. Ties resolve to the lowest value, and slices without any
+    /// entries report a value of 0
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(3)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(0, 10, 5).unwrap();
+    /// h.increment_by(0, 500, 9).unwrap();
+    /// h.increment_by(1_000, 30, 2).unwrap();
+    /// h.increment_by(1_000, 20, 2).unwrap();
+    ///
+    /// assert_eq!(h.mode(), vec![(0, 500), (1_000, 20), (2_000, 0)]);
+    /// ```
+    pub fn mode(&self) -> Vec<(u64, u64)> {
+        self.slices()
+            .map(|slice| {
+                let mut mode = (0, 0);
+                for bucket in slice.histogram() {
+                    if bucket.count() > mode.1 {
+                        mode = (bucket.value(), bucket.count());
+                    }
+                }
+                (slice.start(), mode.0)
+            })
+            .collect()
+    }
+
+    /// returns the largest count of any bucket across the entire `Heatmap`,
+    /// which is useful for normalizing the color range of a visualization
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().start(0).build().unwrap();
+    /// assert_eq!(h.max_bucket_count(), 0);
+    ///
+    /// h.increment_by(0, 10, 5).unwrap();
+    /// h.increment_by(0, 20, 3).unwrap();
+    /// assert_eq!(h.max_bucket_count(), 5);
+    /// ```
+    pub fn max_bucket_count(&self) -> u64 {
+        self.data
+            .data
+            .iter()
+            .flat_map(|histogram| histogram.into_iter().map(|bucket| bucket.count()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// returns the `(slice_start, count)` for each `Slice`, where the count is
+    /// the number of entries in buckets with a value above the `threshold`.
+    /// Values are compared at the precision of the buckets
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(0, 50, 3).unwrap();
+    /// h.increment_by(0, 100, 2).unwrap();
+    /// h.increment_by(0, 150, 1).unwrap();
+    ///
+    /// assert_eq!(h.count_above(100), vec![(0, 1), (1_000, 0)]);
+    /// assert_eq!(h.count_below(100), vec![(0, 3), (1_000, 0)]);
+    /// ```
+    pub fn count_above(&self, threshold: u64) -> Vec<(u64, u64)> {
+        self.count_where(|value| value > threshold)
+    }
+
+    /// returns the `(slice_start, count)` for each `Slice`, where the count is
+    /// the number of entries in buckets with a value below the `threshold`.
+    /// Values are compared at the precision of the buckets
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(1_000, 10, 4).unwrap();
+    /// h.increment_by(1_000, 900, 1).unwrap();
+    ///
+    /// assert_eq!(h.count_below(500), vec![(0, 0), (1_000, 4)]);
+    /// ```
+    pub fn count_below(&self, threshold: u64) -> Vec<(u64, u64)> {
+        self.count_where(|value| value < threshold)
+    }
+
+    /// returns the `(slice_start, fraction)` for each `Slice`, where the
+    /// fraction is the share of its entries which are above the `threshold`,
+    /// from 0.0 to 1.0. Slices without any entries have a fraction of NaN,
+    /// and may be skipped by filtering on `f64::is_nan`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(0, 50, 3).unwrap();
+    /// h.increment_by(0, 150, 1).unwrap();
+    ///
+    /// let fractions = h.fraction_above(100);
+    /// assert_eq!(fractions[0], (0, 0.25));
+    /// assert!(fractions[1].1.is_nan());
+    ///
+    /// let observed: Vec<(u64, f64)> = fractions
+    ///     .into_iter()
+    ///     .filter(|&(_, fraction)| !fraction.is_nan())
+    ///     .collect();
+    /// assert_eq!(observed, vec![(0, 0.25)]);
+    /// ```
+    pub fn fraction_above(&self, threshold: u64) -> Vec<(u64, f64)> {
+        self.count_above(threshold)
+            .into_iter()
+            .zip(self.slices())
+            .map(|((start, count), slice)| match slice.entries() {
+                0 => (start, f64::NAN),
+                entries => (start, count as f64 / entries as f64),
+            })
+            .collect()
+    }
+
+    /// returns the number of entries per second in each `Slice`, as a vector
+    /// of `(slice_start, rate)` pairs in chronological order. Times are taken
+    /// to be in the `time_unit`, and each rate uses the duration of its own
+    /// slice
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(500_000_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(0, 1, 10).unwrap();
+    /// assert_eq!(h.rate(), vec![(0, 20.0), (500_000_000, 0.0)]);
+    /// ```
+    pub fn rate(&self) -> Vec<(u64, f64)> {
+        self.data
+            .data
+            .iter()
+            .enumerate()
+            .map(|(index, histogram)| {
+                let start = self.slice_start(index);
+                let seconds = (self.slice_stop(index) - start) as f64 /
+                    self.config.time_unit.per_second() as f64;
+                (start, histogram.entries() as f64 / seconds)
+            })
+            .collect()
+    }
+
+    // internal function to sum the counts of the buckets in each slice whose
+    // value matches the predicate
+    fn count_where<F: Fn(u64) -> bool>(&self, predicate: F) -> Vec<(u64, u64)> {
+        self.slices()
+            .map(|slice| {
+                let count = slice
+                    .histogram()
+                    .into_iter()
+                    .filter(|bucket| predicate(bucket.value()))
+                    .map(|bucket| bucket.count())
+                    .sum();
+                (slice.start(), count)
+            })
+            .collect()
+    }
+
+    /// returns the most recent `Slice`, which has the highest time bounds
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.latest().unwrap().start(), 9_000);
+    /// assert_eq!(h.oldest().unwrap().start(), 0);
+    /// ```
+    pub fn latest(&self) -> Option<Slice> {
+        self.config.num_slices.checked_sub(1).map(|index| self.slice_at(index))
+    }
+
+    /// returns the least recent `Slice`, which has the lowest time bounds
+    pub fn oldest(&self) -> Option<Slice> {
+        if self.config.num_slices == 0 {
+            None
+        } else {
+            Some(self.slice_at(0))
+        }
+    }
+
+    /// returns the `Slice` with the most entries, or `None` if the `Heatmap`
+    /// is empty. Ties resolve to the earliest `Slice`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(h.busiest_slice().is_none());
+    ///
+    /// h.increment_by(2_000, 1, 5).unwrap();
+    /// h.increment_by(7_000, 1, 5).unwrap();
+    /// h.increment_by(9_000, 1, 1).unwrap();
+    ///
+    /// let busiest = h.busiest_slice().unwrap();
+    /// assert_eq!(busiest.start(), 2_000);
+    /// assert_eq!(busiest.entries(), 5);
+    /// ```
+    pub fn busiest_slice(&self) -> Option<Slice> {
+        let mut busiest = None;
+        let mut most = 0;
+        for (index, histogram) in self.data.data.iter().enumerate() {
+            if histogram.entries() > most {
+                most = histogram.entries();
+                busiest = Some(index);
+            }
+        }
+        busiest.map(|index| self.slice_at(index))
+    }
+
+    /// returns the value at the given percentile for each `Slice`, as a
+    /// vector of `(slice_start, value)` pairs in chronological order. Slices
+    /// without any entries report a value of 0
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(3)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for value in 1..101 {
+    ///     h.increment(0, value).unwrap();
+    ///     h.increment(2_000, value * 10).unwrap();
+    /// }
+    ///
+    /// assert_eq!(h.percentile(50.0).unwrap(), vec![(0, 51), (1_000, 0), (2_000, 510)]);
+    /// assert!(h.percentile(101.0).is_err());
+    /// ```
+    pub fn percentile(&self, percentile: f64) -> Result<Vec<(u64, u64)>, HeatmapError> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(HeatmapError::InvalidPercentile);
+        }
+
+        Ok(self.percentile_iter(percentile).collect())
+    }
+
+    /// returns an iterator over the `(slice_start, value)` at the given
+    /// percentile for each `Slice`, as with `percentile`, but calculating
+    /// each value only as it is reached. Slices without any entries report a
+    /// value of 0, as does every slice if the percentile is not within 0.0 to
+    /// 100.0
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for value in 1..101 {
+    ///     h.increment(1_000, value).unwrap();
+    /// }
+    ///
+    /// let points: Vec<(u64, u64)> = h.percentile_iter(90.0).skip(1).take(2).collect();
+    /// assert_eq!(points, vec![(1_000, 91), (2_000, 0)]);
+    /// ```
+    pub fn percentile_iter(&self, percentile: f64) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.data.data.iter().enumerate().map(move |(index, histogram)| {
+            (self.slice_start(index), histogram.percentile(percentile).unwrap_or(0))
+        })
+    }
+
+    /// returns the difference between the value at the given percentile of
+    /// this `Heatmap` and of another for each `Slice`, as a vector of
+    /// `(slice_start, difference)` pairs in chronological order. Slices are
+    /// aligned by position, and a positive difference means this `Heatmap` is
+    /// higher. Slices without any entries have a value of 0, as for
+    /// `percentile`
+    ///
+    /// # Errors
+    /// returns `InvalidPercentile` if the percentile is not within 0.0 to
+    /// 100.0, and `Incompatible` unless both `Heatmap`s have the same
+    /// `num_slices`, `slice_duration`, `precision`, and `max_value`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut before = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut after = before.clone();
+    ///
+    /// before.increment(0, 100).unwrap();
+    /// before.increment(1_000, 100).unwrap();
+    /// after.increment(0, 80).unwrap();
+    /// after.increment(1_000, 120).unwrap();
+    ///
+    /// let diff = after.percentile_diff(&before, 99.0).unwrap();
+    /// assert_eq!(diff, vec![(0, -20), (1_000, 20)]);
+    /// ```
+    pub fn percentile_diff(
+        &self,
+        other: &Heatmap,
+        percentile: f64,
+    ) -> Result<Vec<(u64, i64)>, HeatmapError> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(HeatmapError::InvalidPercentile);
+        }
+        if !self.same_geometry(other) {
+            return Err(HeatmapError::Incompatible);
+        }
+
+        Ok(
+            self.percentile_iter(percentile)
+                .zip(other.percentile_iter(percentile))
+                .map(|((start, value), (_, other))| {
+                    let difference = i128::from(value) - i128::from(other);
+                    let difference = difference.clamp(i128::from(i64::MIN), i128::from(i64::MAX));
+                    (start, difference as i64)
+                })
+                .collect(),
+        )
+    }
+
+    /// returns the value at the given percentile for each `Slice`, as with
+    /// `percentile`, but interpolated linearly within the bucket containing
+    /// the percentile rather than reporting the value of that bucket. This
+    /// smooths the steps between buckets, and each bucket is taken to span
+    /// from the value of the bucket below it. Slices without any entries
+    /// report a value of 0
+    ///
+    /// # Errors
+    /// returns `InvalidPercentile` if the percentile is not within 0.0 to
+    /// 100.0
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(0, 10, 1).unwrap();
+    /// h.increment_by(0, 20, 3).unwrap();
+    ///
+    /// // the 75th percentile is two thirds of the way through the bucket of 20
+    /// let p75 = h.percentile_interpolated(75.0).unwrap();
+    /// assert_eq!(p75[0].0, 0);
+    /// assert!((p75[0].1 - 19.667).abs() < 0.001);
+    /// assert_eq!(p75[1], (1_000, 0.0));
+    /// ```
+    pub fn percentile_interpolated(&self, percentile: f64) -> Result<Vec<(u64, f64)>, HeatmapError> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(HeatmapError::InvalidPercentile);
+        }
+
+        Ok(
+            self.data
+                .data
+                .iter()
+                .enumerate()
+                .map(|(index, histogram)| {
+                    let value =
+                        interpolated_percentile(histogram, &self.properties.values, percentile);
+                    (self.slice_start(index), value)
+                })
+                .collect(),
+        )
+    }
+
+    /// returns the value at the given percentile over a trailing window of
+    /// slices, as a vector of `(slice_start, value)` pairs in chronological
+    /// order. Each value is calculated from the entries in that `Slice` and
+    /// the `window - 1` slices before it, or as many as there are for the
+    /// first slices. Windows without any entries report a value of 0
+    ///
+    /// # Errors
+    /// returns `InvalidPercentile` if the percentile is not within 0.0 to
+    /// 100.0, and `InvalidWindow` if the window is 0
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(4)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment(0, 10).unwrap();
+    /// h.increment(1_000, 20).unwrap();
+    /// h.increment(2_000, 30).unwrap();
+    ///
+    /// assert_eq!(
+    ///     h.rolling_percentile(0.0, 2).unwrap(),
+    ///     vec![(0, 10), (1_000, 10), (2_000, 20), (3_000, 30)]
+    /// );
+    /// ```
+    pub fn rolling_percentile(
+        &self,
+        percentile: f64,
+        window: usize,
+    ) -> Result<Vec<(u64, u64)>, HeatmapError> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(HeatmapError::InvalidPercentile);
+        }
+        if window == 0 {
+            return Err(HeatmapError::InvalidWindow);
+        }
+
+        // keep a running count for each bucket rather than merging the
+        // histograms, as a merge records each bucket by its reported value,
+        // which above the linear range is counted in the next bucket
+        let values = &self.properties.values;
+        let mut counts = vec![0; values.len()];
+        let mut result = Vec::with_capacity(self.config.num_slices);
+        for index in 0..self.config.num_slices {
+            for (count, bucket) in counts.iter_mut().zip(&self.data.data[index]) {
+                *count += bucket.count();
+            }
+            if index >= window {
+                for (count, bucket) in counts.iter_mut().zip(&self.data.data[index - window]) {
+                    *count -= bucket.count();
+                }
+            }
+            let value = bucket_percentiles(values, &counts, &[percentile])[0];
+            result.push((self.slice_start(index), value));
+        }
+
+        Ok(result)
+    }
+
+    /// returns a `Histogram` of all the entries in the `Heatmap`, ignoring
+    /// time. It uses the same bucketing as each `Slice`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment(0, 1).unwrap();
+    /// h.increment(5_000, 1).unwrap();
+    /// h.increment(9_000, 100).unwrap();
+    ///
+    /// let summary = h.summary();
+    /// assert_eq!(summary.entries(), 3);
+    /// assert_eq!(summary.get(1).unwrap(), 2);
+    /// assert_eq!(summary.maximum().unwrap(), 100);
+    /// ```
+    pub fn summary(&self) -> Histogram {
+        let mut summary = self.config.histogram().unwrap();
+        for histogram in &self.data.data {
+            merge_histogram(&mut summary, histogram, &self.properties.lower);
+        }
+        summary
+    }
+
+    /// returns a `Histogram` of the entries in every `Slice` which overlaps
+    /// the time window `[start, stop)`. Slices which only partially overlap
+    /// the window are included
+    ///
+    /// # Errors
+    /// returns `InvalidWindow` if `start` is not before `stop`, and
+    /// `SampleTooEarly` or `SampleTooLate` if the window is entirely outside
+    /// the `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// use heatmap::{Heatmap, HeatmapError};
+    ///
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for slice in 0..10 {
+    ///     h.increment(slice * 1_000, slice + 1).unwrap();
+    /// }
+    ///
+    /// let window = h.window_histogram(2_500, 5_000).unwrap();
+    /// assert_eq!(window.entries(), 3);
+    /// assert_eq!(window.minimum().unwrap(), 3);
+    /// assert_eq!(window.maximum().unwrap(), 5);
+    ///
+    /// assert_eq!(h.window_histogram(10_000, 20_000).err(), Some(HeatmapError::SampleTooLate));
+    /// ```
+    pub fn window_histogram(&self, start: u64, stop: u64) -> Result<Histogram, HeatmapError> {
+        let range = self.window_indices(start, stop)?;
+        let mut histogram = self.config.histogram().unwrap();
+        for slice in &self.data.data[range] {
+            merge_histogram(&mut histogram, slice, &self.properties.lower);
+        }
+        Ok(histogram)
+    }
+
+    /// returns the number of entries in every `Slice` which overlaps the time
+    /// window `[start, stop)`, or 0 if the window is invalid or entirely
+    /// outside the `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for slice in 0..10 {
+    ///     h.increment_by(slice * 1_000, 1, slice).unwrap();
+    /// }
+    ///
+    /// assert_eq!(h.entries_in_window(8_000, 10_000), 17);
+    /// assert_eq!(h.entries_in_window(0, 1), 0);
+    /// assert_eq!(h.entries_in_window(20_000, 30_000), 0);
+    /// ```
+    pub fn entries_in_window(&self, start: u64, stop: u64) -> u64 {
+        match self.window_indices(start, stop) {
+            Ok(range) => {
+                self.data.data[range]
+                    .iter()
+                    .fold(0, |total, h| total.saturating_add(h.entries()))
+            }
+            Err(_) => 0,
+        }
+    }
+
+    /// merge one Heatmap into another Heatmap. Each bucket is recorded at the
+    /// start time of its `Slice`, so samples from slices which fall outside
+    /// this `Heatmap` are silently dropped. See `try_merge` for a checked
+    /// alternative
+    ///
+    /// # Example
+    /// ```
+    /// extern crate heatmap;
+    /// extern crate time;
+    ///
+    /// let mut a = heatmap::Heatmap::configure()
+    ///     .num_slices(60)
+    ///     .slice_duration(1_000_000_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut b = heatmap::Heatmap::new();
+    ///
+    /// assert_eq!(a.entries(), 0);
+    /// assert_eq!(b.entries(), 0);
+    ///
+    /// let t0 = time::precise_time_ns();
+    /// let t1 = t0 + 1_000_000_000;
+    ///
+    /// let _ = a.increment(t0, 1);
+    /// let _ = b.increment(t0, 1);
+    ///
+    /// assert_eq!(a.entries(), 1);
+    /// assert_eq!(b.entries(), 1);
+    ///
+    /// a.merge(&mut b);
+    ///
+    /// assert_eq!(a.entries(), 2);
+    /// assert_eq!(a.get(t0, 1).unwrap(), 2);
+    /// assert_eq!(a.get(t0, 2).unwrap(), 0);
+    /// assert_eq!(a.get(t1, 1).unwrap(), 0);
+    /// ```
+    pub fn merge(&mut self, other: &Heatmap) {
+        self.merge_indices(other, 0..other.config.num_slices);
+    }
+
+    /// merge only the slices of another Heatmap which overlap the time window
+    /// `[start, stop)`, such as to stitch together overlapping captures
+    /// without counting the overlap twice. Slices which only partially
+    /// overlap the window are merged in full. As with `merge`, samples which
+    /// fall outside this `Heatmap` are silently dropped
+    ///
+    /// # Errors
+    /// returns `InvalidWindow` if `start` is not before `stop`, and
+    /// `SampleTooEarly` or `SampleTooLate` if the window is entirely outside
+    /// the other `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let config = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0);
+    /// let mut a = config.clone().build().unwrap();
+    /// let mut b = config.build().unwrap();
+    ///
+    /// a.increment(2_000, 1).unwrap();
+    /// b.increment(2_000, 1).unwrap();
+    /// b.increment(7_000, 1).unwrap();
+    ///
+    /// // only take the part of `b` which `a` does not already have
+    /// a.merge_range(&b, 5_000, 10_000).unwrap();
+    /// assert_eq!(a.entries(), 2);
+    /// ```
+    pub fn merge_range(&mut self, other: &Heatmap, start: u64, stop: u64) -> Result<(), HeatmapError> {
+        let range = other.window_indices(start, stop)?;
+        self.merge_indices(other, range);
+        Ok(())
+    }
+
+    /// internal function to merge the slices of another Heatmap within a
+    /// range of indices, recording each bucket at the start of its slice
+    fn merge_indices(&mut self, other: &Heatmap, indices: Range<usize>) {
+        for index in indices {
+            let start = other.slice_start(index);
+            for (value, count) in occupied_buckets(&other.data.data[index], &other.properties.lower) {
+                let _ = self.increment_by(start, value, count);
+            }
+        }
+    }
+
+    /// merge one Heatmap into another Heatmap, multiplying each bucket count
+    /// by the `weight` first. Scaled counts are rounded to the nearest whole
+    /// number, with halves rounding away from zero, so buckets which round
+    /// down to zero are not recorded. A negative or NaN `weight` is treated
+    /// as zero. As with `merge`, samples from slices which fall outside this
+    /// `Heatmap` are silently dropped
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut a = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut b = a.clone();
+    ///
+    /// b.increment_by(0, 10, 10).unwrap();
+    /// b.increment_by(0, 20, 1).unwrap();
+    ///
+    /// a.merge_scaled(&b, 0.25);
+    /// assert_eq!(a.entries(), 3);
+    /// assert_eq!(a.get(0, 10).unwrap(), 3);
+    /// assert_eq!(a.get(0, 20).unwrap(), 0);
+    /// ```
+    pub fn merge_scaled(&mut self, other: &Heatmap, weight: f64) {
+        // NaN fails every comparison, so is clamped along with negatives
+        let weight = if weight > 0.0 { weight } else { 0.0 };
+        for slice in other.slices() {
+            let start = slice.start();
+            for (value, count) in occupied_buckets(slice.histogram(), &other.properties.lower) {
+                let count = (count as f64 * weight).round() as u64;
+                if count > 0 {
+                    let _ = self.increment_by(start, value, count);
+                }
+            }
+        }
+    }
+
+    /// merge one Heatmap into another Heatmap, checking up front that no
+    /// samples would be dropped. The destination is unchanged on error
+    ///
+    /// # Errors
+    /// returns `MergeError::Incompatible` unless both `Heatmap`s have the same
+    /// `precision` and `max_value`, and `MergeError::OutOfRange` with the
+    /// number of samples which fall outside of this `Heatmap`'s time range
+    ///
+    /// # Example
+    /// ```
+    /// use heatmap::{Heatmap, MergeError};
+    ///
+    /// let mut a = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut b = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(5_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// b.increment(5_000, 1).unwrap();
+    /// assert_eq!(a.try_merge(&b), Ok(()));
+    /// assert_eq!(a.entries(), 1);
+    ///
+    /// b.increment_by(14_000, 1, 3).unwrap();
+    /// assert_eq!(a.try_merge(&b), Err(MergeError::OutOfRange { dropped: 3 }));
+    /// assert_eq!(a.entries(), 1);
+    /// ```
+    pub fn try_merge(&mut self, other: &Heatmap) -> Result<(), MergeError> {
+        if self.config.precision != other.config.precision ||
+            self.config.max_value != other.config.max_value
+        {
+            return Err(MergeError::Incompatible);
+        }
+
+        let dropped = other
+            .slices()
+            .filter(|s| s.start() < self.data.start || s.start() >= self.data.stop)
+            .fold(0_u64, |total, s| total.saturating_add(s.entries()));
+        if dropped > 0 {
+            return Err(MergeError::OutOfRange { dropped });
+        }
+
+        self.merge(other);
+        Ok(())
+    }
+
+    /// merge one Heatmap into another Heatmap by slice index, ignoring the
+    /// absolute time of each `Slice`. This is useful for combining heatmaps
+    /// which were recorded with different start times
+    ///
+    /// # Errors
+    /// returns `MergeError::Incompatible` unless both `Heatmap`s have the same
+    /// `precision` and `max_value`, and `MergeError::Misaligned` unless they
+    /// have the same `num_slices` and `slice_duration`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut a = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut b = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(1_000_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// b.increment(1_002_000, 1).unwrap();
+    /// a.merge_aligned(&b).unwrap();
+    ///
+    /// assert_eq!(a.entries(), 1);
+    /// assert_eq!(a.get(2_000, 1).unwrap(), 1);
+    /// ```
+    pub fn merge_aligned(&mut self, other: &Heatmap) -> Result<(), MergeError> {
+        if self.config.precision != other.config.precision ||
+            self.config.max_value != other.config.max_value
+        {
+            return Err(MergeError::Incompatible);
+        }
+        if !self.same_geometry(other) {
+            return Err(MergeError::Misaligned);
+        }
+
+        let lower = &self.properties.lower;
+        for (histogram, other) in self.data.data.iter_mut().zip(&other.data.data) {
+            merge_histogram(histogram, other, lower);
+            self.data.counters.add(other.entries());
+        }
+
+        Ok(())
+    }
+
+    /// merge many `Heatmap`s into a new one by slice index, as with
+    /// `merge_aligned`. With the `parallel` feature, when there are enough
+    /// buckets to merge, the slices are divided between threads, with each
+    /// thread merging every input for its own range of slices. The result
+    /// takes its start time from the first `Heatmap`
+    ///
+    /// # Errors
+    /// returns `Incompatible` if `heatmaps` is empty, or unless every
+    /// `Heatmap` has the same `num_slices`, `slice_duration`, `precision`,
+    /// and `max_value`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let config = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0);
+    ///
+    /// let mut heatmaps = Vec::new();
+    /// for i in 0..8 {
+    ///     let mut h = config.clone().build().unwrap();
+    ///     h.increment(i * 1_000, 1).unwrap();
+    ///     h.increment(9_000, 1_000_000).unwrap();
+    ///     heatmaps.push(h);
+    /// }
+    ///
+    /// let h = Heatmap::merge_all(&heatmaps).unwrap();
+    /// assert_eq!(h.entries(), 16);
+    /// assert_eq!(h.get(3_000, 1).unwrap(), 1);
+    /// assert_eq!(h.get(9_000, 1_000_000).unwrap(), 8);
+    /// assert!(Heatmap::merge_all(&[]).is_err());
+    /// ```
+    pub fn merge_all(heatmaps: &[Heatmap]) -> Result<Heatmap, HeatmapError> {
+        let (first, rest) = match heatmaps.split_first() {
+            Some(split) => split,
+            None => return Err(HeatmapError::Incompatible),
+        };
+        if rest.iter().any(|other| !first.same_geometry(other)) {
+            return Err(HeatmapError::Incompatible);
+        }
+
+        let mut heatmap = first.clone();
+        let buckets = rest.len() as u64 * first.config.num_slices as u64 * first.properties.buckets;
+        let threads = if !cfg!(feature = "parallel") || buckets < PARALLEL_MERGE_BUCKETS {
+            1
+        } else {
+            thread::available_parallelism().map_or(1, |n| n.get())
+        };
+        let chunk = heatmap.data.data.len().div_ceil(threads);
+        let values = &first.properties.lower;
+
+        // merge every input into a range of slices starting at a chunk index
+        let merge = |i: usize, histograms: &mut [Histogram]| {
+            for (offset, histogram) in histograms.iter_mut().enumerate() {
+                let index = i * chunk + offset;
+                for other in rest {
+                    merge_histogram(histogram, &other.data.data[index], values);
+                }
+            }
+        };
+
+        if threads == 1 {
+            merge(0, &mut heatmap.data.data);
+        } else {
+            let merge = &merge;
+            thread::scope(|scope| {
+                for (i, histograms) in heatmap.data.data.chunks_mut(chunk).enumerate() {
+                    scope.spawn(move || merge(i, histograms));
+                }
+            });
+        }
+
+        heatmap.recount();
+        Ok(heatmap)
+    }
+
+    /// subtract the counts of another `Heatmap` from this one, slice by slice.
+    /// Slices are aligned by position rather than by time, and counts
+    /// saturate at zero
+    ///
+    /// # Errors
+    /// returns `Incompatible` unless both `Heatmap`s have the same
+    /// `num_slices`, `slice_duration`, `precision`, and `max_value`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut a = Heatmap::configure().start(0).build().unwrap();
+    /// let mut b = Heatmap::configure().start(0).build().unwrap();
+    ///
+    /// a.increment_by(0, 1, 5).unwrap();
+    /// b.increment_by(0, 1, 2).unwrap();
+    /// b.increment_by(0, 2, 2).unwrap();
+    ///
+    /// a.subtract(&b).unwrap();
+    /// assert_eq!(a.get(0, 1).unwrap(), 3);
+    /// assert_eq!(a.get(0, 2).unwrap(), 0);
+    /// assert_eq!(a.entries(), 3);
+    /// ```
+    pub fn subtract(&mut self, other: &Heatmap) -> Result<(), HeatmapError> {
+        if !self.same_geometry(other) {
+            return Err(HeatmapError::Incompatible);
+        }
+
+        let values = &self.properties.lower;
+        for (histogram, other) in self.data.data.iter_mut().zip(&other.data.data) {
+            let buckets: Vec<(u64, u64)> = histogram
+                .into_iter()
+                .zip(other)
+                .zip(values)
+                .map(|((bucket, other), value)| (*value, cmp::min(bucket.count(), other.count())))
+                .filter(|&(_, count)| count > 0)
+                .collect();
+            for (value, count) in buckets {
+                if histogram.decrement_by(value, count).is_ok() {
+                    self.data.counters.entries_total =
+                        self.data.counters.entries_total.saturating_sub(count);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// multiply every bucket count by the `factor`, rounding down, so that
+    /// calling it periodically gradually ages out older samples. The factor
+    /// is clamped to the range 0.0 to 1.0, with NaN treated as zero
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().start(0).build().unwrap();
+    ///
+    /// h.increment_by(0, 1, 10).unwrap();
+    /// h.increment_by(0, 2, 1).unwrap();
+    ///
+    /// h.decay(0.5);
+    /// assert_eq!(h.get(0, 1).unwrap(), 5);
+    /// assert_eq!(h.get(0, 2).unwrap(), 0);
+    /// assert_eq!(h.entries(), 5);
+    /// ```
+    pub fn decay(&mut self, factor: f64) {
+        let factor = if factor > 0.0 { factor.min(1.0) } else { 0.0 };
+        let values = &self.properties.lower;
+        for histogram in &mut self.data.data {
+            let buckets = occupied_buckets(histogram, values);
+            for (value, count) in buckets {
+                // rounding may scale a large count above itself
+                let removed = count.saturating_sub((count as f64 * factor) as u64);
+                if removed > 0 && histogram.decrement_by(value, removed).is_ok() {
+                    self.data.counters.entries_total =
+                        self.data.counters.entries_total.saturating_sub(removed);
+                }
+            }
+        }
+    }
+
+    /// keep only the buckets for which the closure returns true when called
+    /// with `(slice_start, value, count)`, clearing all others. The closure is
+    /// only called for buckets which have a count
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(0, 10, 2).unwrap();
+    /// h.increment_by(0, 900, 1).unwrap();
+    /// h.increment_by(1_000, 950, 3).unwrap();
+    ///
+    /// // drop the bogus samples above 500
+    /// h.retain(|_, value, _| value <= 500);
+    /// assert_eq!(h.entries(), 2);
+    /// assert_eq!(h.get(1_000, 950).unwrap(), 0);
+    /// ```
+    pub fn retain<F: FnMut(u64, u64, u64) -> bool>(&mut self, mut f: F) {
+        let values = &self.properties.lower;
+        for index in 0..self.config.num_slices {
+            let start = self.slice_start(index);
+            let histogram = &mut self.data.data[index];
+            let buckets: Vec<(u64, u64, u64)> = histogram
+                .into_iter()
+                .zip(values)
+                .filter(|&(bucket, _)| bucket.count() > 0)
+                .map(|(bucket, value)| (bucket.value(), *value, bucket.count()))
+                .collect();
+            for (reported, value, count) in buckets {
+                if !f(start, reported, count) && histogram.decrement_by(value, count).is_ok() {
+                    self.data.counters.entries_total =
+                        self.data.counters.entries_total.saturating_sub(count);
+                }
+            }
+        }
+    }
+
+    /// replace the count of every bucket which has a count with the result of
+    /// the closure, such as to halve or cap the counts. Empty buckets are left
+    /// empty. The total number of entries is adjusted by the change in each
+    /// bucket, so it remains the sum of the bucket counts
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().start(0).build().unwrap();
+    ///
+    /// h.increment_by(0, 1, 10).unwrap();
+    /// h.increment_by(0, 2, 3).unwrap();
+    ///
+    /// h.map_counts(|count| std::cmp::min(count, 5));
+    /// assert_eq!(h.get(0, 1).unwrap(), 5);
+    /// assert_eq!(h.get(0, 2).unwrap(), 3);
+    /// assert_eq!(h.entries(), 8);
+    /// ```
+    pub fn map_counts<F: FnMut(u64) -> u64>(&mut self, mut f: F) {
+        let values = &self.properties.lower;
+        for histogram in &mut self.data.data {
+            for (value, count) in occupied_buckets(histogram, values) {
+                let mapped = f(count);
+                if mapped < count {
+                    if histogram.decrement_by(value, count - mapped).is_ok() {
+                        self.data.counters.entries_total =
+                            self.data.counters.entries_total.saturating_sub(count - mapped);
+                    }
+                } else if mapped > count && histogram.increment_by(value, mapped - count).is_ok() {
+                    self.data.counters.add(mapped - count);
+                }
+            }
+        }
+    }
+
+    /// returns a new `Heatmap` with fewer, longer `Slice`s, where each merges
+    /// `factor` consecutive slices of this one
+    ///
+    /// # Errors
+    /// returns `InvalidFactor` if `factor` is 0 or does not evenly divide
+    /// `num_slices`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(60)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for slice in 0..60 {
+    ///     h.increment(slice * 1_000, slice).unwrap();
+    /// }
+    ///
+    /// let minutes = h.downsample(20).unwrap();
+    /// assert_eq!(minutes.num_slices(), 3);
+    /// assert_eq!(minutes.entries(), 60);
+    /// assert_eq!(minutes.latest().unwrap().start(), 40_000);
+    /// assert_eq!(minutes.latest().unwrap().entries(), 20);
+    ///
+    /// assert!(h.downsample(7).is_err());
+    /// ```
+    pub fn downsample(&self, factor: usize) -> Result<Heatmap, HeatmapError> {
+        if factor == 0 || self.config.num_slices % factor != 0 {
+            return Err(HeatmapError::InvalidFactor);
+        }
+
+        let config = if self.properties.uniform {
+            self.config
+                .clone()
+                .slice_duration(self.config.slice_duration * factor as u64)
+                .num_slices(self.config.num_slices / factor)
+                .start(self.data.start)
+        } else {
+            let boundaries = self.properties
+                .offsets
+                .iter()
+                .step_by(factor)
+                .map(|offset| self.data.start + offset)
+                .collect();
+            self.config.clone().boundaries(boundaries)
+        };
+        let mut heatmap = config.build().map_err(|_| HeatmapError::InvalidFactor)?;
+
+        let lower = &self.properties.lower;
+        for (index, histogram) in self.data.data.iter().enumerate() {
+            merge_histogram(&mut heatmap.data.data[index / factor], histogram, lower);
+        }
+        heatmap.data.counters = self.data.counters;
+
+        Ok(heatmap)
+    }
+
+    /// returns a new `Heatmap` which spans only from the first `Slice` with
+    /// entries to the last, dropping the empty slices before and after them.
+    /// The `slice_duration` is unchanged. A `Heatmap` without any entries is
+    /// returned as it is
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(60)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment(3_000, 1).unwrap();
+    /// h.increment(5_500, 1).unwrap();
+    ///
+    /// let trimmed = h.trimmed();
+    /// assert_eq!(trimmed.num_slices(), 3);
+    /// assert_eq!(trimmed.start_time(), 3_000);
+    /// assert_eq!(trimmed.stop_time(), 6_000);
+    /// assert_eq!(trimmed.entries(), 2);
+    /// ```
+    pub fn trimmed(&self) -> Heatmap {
+        let occupied = |histogram: &Histogram| histogram.entries() > 0;
+        let (first, last) = match (
+            self.data.data.iter().position(occupied),
+            self.data.data.iter().rposition(occupied),
+        ) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return self.clone(),
+        };
+
+        let config = if self.properties.uniform {
+            self.config
+                .clone()
+                .num_slices(last + 1 - first)
+                .start(self.slice_start(first))
+        } else {
+            let boundaries = self.properties.offsets[first..last + 2]
+                .iter()
+                .map(|offset| self.data.start + offset)
+                .collect();
+            self.config.clone().boundaries(boundaries)
+        };
+        // fewer slices only raise the memory bound for each, so the config
+        // remains valid
+        let mut heatmap = config.build().unwrap();
+
+        heatmap.data.data = self.data.data[first..last + 1].to_vec();
+        heatmap.data.counters = self.data.counters;
+        heatmap.data.anchor = self.data.anchor;
+        heatmap
+    }
+
+    /// returns a new `Heatmap` with the same start time but a different slice
+    /// geometry. Each `Slice` of this heatmap is attributed entirely to the
+    /// new slice which contains its start time, even if it straddles two new
+    /// slices. Slices which start after the end of the new heatmap are dropped
+    ///
+    /// # Errors
+    /// returns a `ConfigError` if the new geometry is invalid
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for slice in 0..10 {
+    ///     h.increment(slice * 1_000, 1).unwrap();
+    /// }
+    ///
+    /// // new slices start at 0, 1_500, 3_000, and 4_500, so the old slices
+    /// // starting at 6_000 and later are dropped
+    /// let r = h.resample(1_500, 4).unwrap();
+    /// let entries: Vec<u64> = r.into_iter().map(|s| s.entries()).collect();
+    /// assert_eq!(entries, vec![2, 1, 2, 1]);
+    /// assert_eq!(r.entries(), 6);
+    /// ```
+    pub fn resample(&self, slice_duration: u64, num_slices: usize) -> Result<Heatmap, ConfigError> {
+        let mut config = self.config.clone();
+        config.boundaries = None;
+        let mut heatmap = config
+            .slice_duration(slice_duration)
+            .num_slices(num_slices)
+            .start(self.data.start)
+            .build()?;
+
+        for slice in self.slices() {
+            if let Ok(index) = heatmap.histogram_index(slice.start()) {
+                let lower = &self.properties.lower;
+                merge_histogram(&mut heatmap.data.data[index], slice.histogram(), lower);
+                heatmap.data.counters.add(slice.entries());
+            }
+        }
+
+        Ok(heatmap)
+    }
+
+    /// build a `Heatmap` from the `Histogram` of each `Slice`, such as those
+    /// produced by another stage of a pipeline, in chronological order. The
+    /// `num_slices` is the number of histograms, and as `Histogram` does not
+    /// expose its config, the `precision` and `max_value` are inferred from
+    /// the buckets of the first
+    ///
+    /// # Errors
+    /// returns `InvalidConfig` if there are no histograms or the geometry is
+    /// otherwise invalid, and `Incompatible` if the histograms do not all
+    /// share the same buckets, or the buckets do not match any `precision`
+    ///
+    /// # Example
+    /// ```
+    /// extern crate heatmap;
+    /// extern crate histogram;
+    ///
+    /// use heatmap::Heatmap;
+    /// use histogram::Histogram;
+    ///
+    /// let mut histograms = Vec::new();
+    /// for i in 0..10 {
+    ///     let mut histogram = Histogram::configure()
+    ///         .precision(2)
+    ///         .max_value(100_000)
+    ///         .build()
+    ///         .unwrap();
+    ///     histogram.increment_by(i, 10).unwrap();
+    ///     histograms.push(histogram);
+    /// }
+    ///
+    /// let h = Heatmap::from_histograms(histograms, 5_000, 1_000).unwrap();
+    /// assert_eq!(h.config().get_precision(), 2);
+    /// assert_eq!(h.config().get_max_value(), 100_000);
+    /// assert_eq!(h.num_slices(), 10);
+    /// assert_eq!(h.entries(), 100);
+    /// assert_eq!(h.get(8_000, 3).unwrap(), 10);
+    /// ```
+    pub fn from_histograms(
+        histograms: Vec<Histogram>,
+        start: u64,
+        slice_duration: u64,
+    ) -> Result<Heatmap, HeatmapError> {
+        let values: Vec<u64> = match histograms.first() {
+            Some(histogram) => histogram.into_iter().map(|bucket| bucket.value()).collect(),
+            None => return Err(HeatmapError::InvalidConfig(ConfigError::NumSlices)),
+        };
+        let precision = infer_precision(&values).ok_or(HeatmapError::Incompatible)?;
+
+        let mut heatmap = Heatmap::configure()
+            .precision(precision)
+            .max_value(values[values.len() - 1])
+            .slice_duration(slice_duration)
+            .num_slices(histograms.len())
+            .start(start)
+            .build()?;
+
+        let values = &heatmap.properties.values;
+        let matching = |histogram: &Histogram| {
+            histogram.into_iter().map(|bucket| bucket.value()).eq(values.iter().cloned())
+        };
+        if !histograms.iter().all(matching) {
+            return Err(HeatmapError::Incompatible);
+        }
+
+        heatmap.data.data = histograms;
+        heatmap.recount();
+        Ok(heatmap)
+    }
+
+    /// internal function to check that two heatmaps have slices and buckets
+    /// which line up
+    fn same_geometry(&self, other: &Heatmap) -> bool {
+        self.config.num_slices == other.config.num_slices &&
+            self.config.slice_duration == other.config.slice_duration &&
+            self.config.precision == other.config.precision &&
+            self.config.max_value == other.config.max_value &&
+            self.properties.offsets == other.properties.offsets
+    }
+
+    /// internal function to reject saving a `Heatmap` with non-uniform
+    /// slices, which only the JSON format can represent
+    fn check_uniform(&self) -> io::Result<()> {
+        if self.properties.uniform {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "non-uniform slice boundaries can only be saved as json",
+            ))
+        }
+    }
+
+    /// save the `Heatmap` to disk. NOTE: format may change in future
+    ///
+    /// # Errors
+    /// returns any IO error encountered while creating or writing the file,
+    /// and `InvalidInput` if the `Heatmap` has non-uniform slice boundaries
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.check_uniform()?;
+        self.save_to(&mut BufWriter::new(File::create(path)?))
+    }
+
+    /// write the `Heatmap` in the format used by `save` to any writer, such
+    /// as an in-memory buffer or a socket. The writer is flushed at the end
+    ///
+    /// # Errors
+    /// returns any IO error encountered while writing, and `InvalidInput` if
+    /// the `Heatmap` has non-uniform slice boundaries
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut a = Heatmap::configure().start(0).build().unwrap();
+    /// a.increment_by(0, 1_000, 5).unwrap();
+    ///
+    /// let mut buffer = Vec::new();
+    /// a.save_to(&mut buffer).unwrap();
+    ///
+    /// let b = Heatmap::load_from(&buffer[..]).unwrap();
+    /// assert_eq!(b, a);
+    /// ```
+    pub fn save_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.check_uniform()?;
+
+        writeln!(
+            writer,
+            "{} {} {} {:?} {} {:?}",
+            self.config.precision,
+            self.config.max_memory,
+            self.config.max_value,
+            self.config.slice_duration,
+            self.config.num_slices,
+            self.data.start
+        )?;
+
+        for (start, value, count) in self.cells() {
+            writeln!(writer, "{:?} {} {}", start, value, count)?;
+        }
+
+        writer.flush()
+    }
+
+    /// load the `Heatmap` from file. NOTE: format may change in future
+    ///
+    /// # Errors
+    /// returns a `LoadError` if the file cannot be read, or if it does not
+    /// contain a valid `Heatmap`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Heatmap, LoadError> {
+        Heatmap::load_from(BufReader::new(File::open(path)?))
+    }
+
+    /// read a `Heatmap` in the format used by `save` from any buffered
+    /// reader, such as an in-memory buffer or a socket
+    ///
+    /// # Errors
+    /// returns a `LoadError` if the input cannot be read, or if it does not
+    /// contain a valid `Heatmap`
+    pub fn load_from<R: BufRead>(reader: R) -> Result<Heatmap, LoadError> {
+        let mut lines = reader.lines();
+
+        let config = match lines.next() {
+            Some(line) => line?,
+            None => return Err(LoadError::Malformed { line: 1 }),
+        };
+        let config_tokens: Vec<&str> = config.split_whitespace().collect();
+        if config_tokens.len() != 6 {
+            return Err(LoadError::Malformed { line: 1 });
+        }
+
+        let precision: u32 = parse_token(config_tokens[0], 1)?;
+        let max_memory: u32 = parse_token(config_tokens[1], 1)?;
+        let max_value: u64 = parse_token(config_tokens[2], 1)?;
+        let slice_duration: u64 = parse_token(config_tokens[3], 1)?;
+        let num_slices: usize = parse_token(config_tokens[4], 1)?;
+        let start: u64 = parse_token(config_tokens[5], 1)?;
+
+        let mut heatmap = Heatmap::configure()
+            .precision(precision)
+            .max_memory(max_memory)
+            .max_value(max_value)
+            .slice_duration(slice_duration)
+            .num_slices(num_slices)
+            .start(start)
             .build()
-            .unwrap();
+            .map_err(|_| LoadError::Malformed { line: 1 })?;
 
-        for line in lines {
-            if let Ok(s) = line {
-                let tokens: Vec<&str> = s.split_whitespace().collect();
-                if tokens.len() != 3 {
-                    panic!("malformed heatmap file");
-                }
-                let start: u64 = tokens[0].parse().unwrap();
-                let value: u64 = tokens[1].parse().unwrap();
-                let count: u64 = tokens[2].parse().unwrap();
+        for (index, line) in lines.enumerate() {
+            let line_number = index + 2;
+            let (start, value, count) = parse_record(&line?, line_number)?;
+            let _ = heatmap.increment_by(start, value, count);
+        }
+
+        Ok(heatmap)
+    }
+
+    /// save the `Heatmap` to disk in a compact binary format. The file begins
+    /// with a magic number and a format version, followed by the config and
+    /// the non-zero buckets of each `Slice` encoded as varints. Each bucket is
+    /// written as the smallest value which it counts and its count
+    ///
+    /// # Errors
+    /// returns any IO error encountered while creating or writing the file,
+    /// and `InvalidInput` if the `Heatmap` has non-uniform slice boundaries
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut a = Heatmap::configure().start(0).build().unwrap();
+    /// a.increment_by(0, 1_000, 5).unwrap();
+    ///
+    /// let path = std::env::temp_dir().join("heatmap_doc_binary.bin");
+    /// a.save_binary(&path).unwrap();
+    ///
+    /// let b = Heatmap::load_binary(&path).unwrap();
+    /// assert_eq!(b.entries(), 5);
+    /// assert_eq!(b.to_json(), a.to_json());
+    /// ```
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.check_uniform()?;
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(binary::MAGIC)?;
+        writer.write_all(&[binary::VERSION])?;
+
+        binary::write_u32(&mut writer, self.config.precision)?;
+        binary::write_u32(&mut writer, self.config.max_memory)?;
+        binary::write_u64(&mut writer, self.config.max_value)?;
+        binary::write_u64(&mut writer, self.config.slice_duration)?;
+        binary::write_u64(&mut writer, self.config.num_slices as u64)?;
+        binary::write_u64(&mut writer, self.data.start)?;
+
+        for histogram in &self.data.data {
+            let buckets = occupied_buckets(histogram, &self.properties.lower);
+            binary::write_varint(&mut writer, buckets.len() as u64)?;
+            for (value, count) in buckets {
+                binary::write_varint(&mut writer, value)?;
+                binary::write_varint(&mut writer, count)?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// load a `Heatmap` which was saved with `save_binary`
+    ///
+    /// # Errors
+    /// returns a `LoadError` if the file cannot be read, was written with an
+    /// unsupported format version, or does not contain a valid `Heatmap`
+    pub fn load_binary<P: AsRef<Path>>(path: P) -> Result<Heatmap, LoadError> {
+        let mut reader = binary::Reader::new(BufReader::new(File::open(path)?));
+
+        let mut magic = [0; 4];
+        reader.bytes(&mut magic)?;
+        if &magic != binary::MAGIC {
+            return Err(LoadError::Binary { offset: 0 });
+        }
+
+        let version = reader.u8()?;
+        if version != binary::VERSION {
+            return Err(LoadError::UnsupportedVersion { version });
+        }
+
+        let header = reader.error();
+        let precision = reader.u32()?;
+        let max_memory = reader.u32()?;
+        let max_value = reader.u64()?;
+        let slice_duration = reader.u64()?;
+        let num_slices = reader.u64()?;
+        let start = reader.u64()?;
+
+        if num_slices > usize::MAX as u64 {
+            return Err(header);
+        }
+
+        let mut heatmap = Heatmap::configure()
+            .precision(precision)
+            .max_memory(max_memory)
+            .max_value(max_value)
+            .slice_duration(slice_duration)
+            .num_slices(num_slices as usize)
+            .start(start)
+            .build()
+            .map_err(|_| header)?;
+
+        for index in 0..heatmap.config.num_slices {
+            let start = heatmap.slice_start(index);
+            let buckets = reader.varint()?;
+            for _ in 0..buckets {
+                let value = reader.varint()?;
+                let count = reader.varint()?;
                 let _ = heatmap.increment_by(start, value, count);
             }
         }
+        reader.finish()?;
 
-        heatmap
+        Ok(heatmap)
+    }
+
+    /// render the `Heatmap` as a PNG image. Columns are `Slice`s from oldest
+    /// on the left to latest on the right, and rows are value buckets from
+    /// the smallest values at the bottom, following the bucketing of the
+    /// underlying `Histogram`s. Each pixel is colored by the largest count of
+    /// the cells it covers, normalized against the largest count of any cell
+    ///
+    /// # Errors
+    /// returns an error if `width` or `height` is 0, or any IO error
+    /// encountered while creating or writing the file
+    ///
+    /// # Example
+    /// ```
+    /// use heatmap::{Colormap, Heatmap};
+    ///
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(60)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for slice in 0..60 {
+    ///     h.increment_by(slice * 1_000, slice * 1_000, slice + 1).unwrap();
+    /// }
+    ///
+    /// let path = std::env::temp_dir().join("heatmap_doc.png");
+    /// h.save_png(&path, 120, 100, Colormap::Viridis).unwrap();
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn save_png<P: AsRef<Path>>(
+        &self,
+        path: P,
+        width: u32,
+        height: u32,
+        colormap: Colormap,
+    ) -> io::Result<()> {
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "image dimensions must be non-zero",
+            ));
+        }
+
+        let slices = self.config.num_slices;
+        let buckets = self.data.data[0].buckets_total() as usize;
+        let (width, height) = (width as usize, height as usize);
+
+        // the range of cells covered by a pixel along an axis, which always
+        // includes at least one cell
+        let cells = |pixel: usize, pixels: usize, cells: usize| {
+            let first = pixel * cells / pixels;
+            first..cmp::max((pixel + 1) * cells / pixels, first + 1)
+        };
+
+        // the largest count in each bucket, for the slices of each column
+        let mut columns = vec![vec![0_u64; buckets]; width];
+        for (x, column) in columns.iter_mut().enumerate() {
+            for histogram in &self.data.data[cells(x, width, slices)] {
+                for (count, bucket) in column.iter_mut().zip(histogram) {
+                    *count = cmp::max(*count, bucket.count());
+                }
+            }
+        }
+
+        let max = self.max_bucket_count();
+
+        let mut pixels = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            let row = cells(height - 1 - y, height, buckets);
+            for column in &columns {
+                let count = column[row.clone()].iter().cloned().max().unwrap_or(0);
+                let intensity = if max > 0 {
+                    count as f64 / max as f64
+                } else {
+                    0.0
+                };
+                pixels.extend_from_slice(&colormap.color(intensity));
+            }
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        png::write_rgb(&mut writer, width as u32, height as u32, &pixels)?;
+        writer.flush()
+    }
+
+    /// export the `Heatmap` as CSV, with a header row followed by one row per
+    /// `Slice` holding its start, stop, entry count, and the value at each of
+    /// the given percentiles. Percentile cells are left empty for slices
+    /// without any entries, and for percentiles outside 0.0 to 100.0
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for value in 1..101 {
+    ///     h.increment(0, value).unwrap();
+    /// }
+    ///
+    /// assert_eq!(
+    ///     h.to_csv(&[50.0, 99.9]),
+    ///     "start,stop,count,p50,p99.9\n0,1000,100,51,100\n1000,2000,0,,\n"
+    /// );
+    /// ```
+    pub fn to_csv(&self, percentiles: &[f64]) -> String {
+        let mut csv = String::from("start,stop,count");
+        for percentile in percentiles {
+            csv.push_str(&format!(",p{}", percentile));
+        }
+        csv.push('\n');
+
+        for slice in self.slices() {
+            csv.push_str(&format!("{},{},{}", slice.start(), slice.stop(), slice.entries()));
+            let values = histogram_percentiles(slice.histogram(), percentiles);
+            for (percentile, value) in percentiles.iter().zip(values) {
+                if slice.entries() > 0 && (0.0..=100.0).contains(percentile) {
+                    csv.push_str(&format!(",{}", value));
+                } else {
+                    csv.push(',');
+                }
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// serialize the `Heatmap` as JSON, including only buckets which have a
+    /// non-zero count. Each bucket is written as the smallest value which it
+    /// counts, rather than its reported value, so that loading it restores
+    /// the same bucket
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .max_value(1_000)
+    ///     .slice_duration(1_000)
+    ///     .num_slices(2)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// h.increment_by(1_000, 10, 2).unwrap();
+    ///
+    /// assert_eq!(
+    ///     h.to_json(),
+    ///     "{\"config\":{\"precision\":3,\"max_memory\":0,\"max_value\":1000,\
+    ///      \"slice_duration\":1000,\"num_slices\":2,\"start\":0},\
+    ///      \"slices\":[{\"start\":0,\"buckets\":[]},{\"start\":1000,\"buckets\":[[10,2]]}]}"
+    /// );
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"config\":{{\"precision\":{},\"max_memory\":{},\"max_value\":{},\
+             \"slice_duration\":{},\"num_slices\":{},\"start\":{}",
+            self.config.precision,
+            self.config.max_memory,
+            self.config.max_value,
+            self.config.slice_duration,
+            self.config.num_slices,
+            self.data.start
+        );
+        if !self.properties.uniform {
+            let boundaries: Vec<String> = (0..self.config.num_slices + 1)
+                .map(|index| self.slice_start(index).to_string())
+                .collect();
+            json.push_str(&format!(",\"boundaries\":[{}]", boundaries.join(",")));
+        }
+        json.push_str("},\"slices\":[");
+
+        for (index, histogram) in self.data.data.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!("{{\"start\":{},\"buckets\":[", self.slice_start(index)));
+            let buckets: Vec<String> = occupied_buckets(histogram, &self.properties.lower)
+                .into_iter()
+                .map(|(value, count)| format!("[{},{}]", value, count))
+                .collect();
+            json.push_str(&buckets.join(","));
+            json.push_str("]}");
+        }
+        json.push_str("]}");
+
+        json
+    }
+
+    /// deserialize a `Heatmap` from JSON produced by `to_json`
+    ///
+    /// # Errors
+    /// returns `LoadError::Json` with the offset of the problem if the JSON
+    /// is malformed or does not describe a valid `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut a = Heatmap::configure().start(0).build().unwrap();
+    /// a.increment(0, 1).unwrap();
+    ///
+    /// let b = Heatmap::from_json(&a.to_json()).unwrap();
+    /// assert_eq!(b.entries(), 1);
+    /// assert_eq!(b.to_json(), a.to_json());
+    /// ```
+    pub fn from_json(json: &str) -> Result<Heatmap, LoadError> {
+        let mut parser = json::Parser::new(json);
+
+        let mut config = None;
+        let mut cells = Vec::new();
+
+        parser.object(|parser, key| match key {
+            "config" => {
+                config = Some(parse_json_config(parser)?);
+                Ok(())
+            }
+            "slices" => {
+                parser.array(|parser| {
+                    let mut start = None;
+                    let mut buckets = Vec::new();
+                    parser.object(|parser, key| match key {
+                        "start" => {
+                            start = Some(parser.number()?);
+                            Ok(())
+                        }
+                        "buckets" => {
+                            parser.array(|parser| {
+                                let mut pair = Vec::new();
+                                parser.array(|parser| {
+                                    pair.push(parser.number()?);
+                                    Ok(())
+                                })?;
+                                if pair.len() != 2 {
+                                    return Err(parser.error());
+                                }
+                                buckets.push((pair[0], pair[1]));
+                                Ok(())
+                            })
+                        }
+                        _ => Err(parser.error()),
+                    })?;
+                    let start = start.ok_or_else(|| parser.error())?;
+                    for (value, count) in buckets.drain(..) {
+                        cells.push((start, value, count));
+                    }
+                    Ok(())
+                })
+            }
+            _ => Err(parser.error()),
+        })?;
+        parser.finish()?;
+
+        let mut heatmap = match config {
+            Some(Some(heatmap)) => heatmap,
+            _ => return Err(LoadError::Json { offset: 0 }),
+        };
+
+        for (start, value, count) in cells {
+            let _ = heatmap.increment_by(start, value, count);
+        }
+
+        Ok(heatmap)
     }
 
     /// returns the number of buckets per `Histogram` / `Slice`
     pub fn histogram_buckets(&self) -> u64 {
-        self.data.data[0].clone().buckets_total()
+        self.properties.buckets
+    }
+
+    /// returns the start time of every `Slice` in chronological order, which
+    /// are the columns of the `Heatmap`. The offsets of the slices are fixed
+    /// when it is built, but a `Heatmap` may be moved in time by `rebase` or
+    /// in circular mode, so the start times are returned as a new vector
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(3)
+    ///     .start(5_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.time_boundaries(), vec![5_000, 6_000, 7_000]);
+    /// ```
+    pub fn time_boundaries(&self) -> Vec<u64> {
+        self.properties.offsets[..self.config.num_slices]
+            .iter()
+            .map(|offset| self.data.start + offset)
+            .collect()
+    }
+
+    /// returns the lower bound of every bucket in ascending order, which is
+    /// the smallest value each counts and the value returned for it by
+    /// `quantize_value`. All `Slice`s share the same buckets, so these are
+    /// the rows of the `Heatmap`
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .precision(1)
+    ///     .max_value(100)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let values = h.value_boundaries();
+    /// assert_eq!(values.len() as u64, h.histogram_buckets());
+    /// assert_eq!(values[0], 0);
+    /// assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+    /// ```
+    pub fn value_boundaries(&self) -> &[u64] {
+        &self.properties.lower
+    }
+
+    /// returns the smallest value which is counted in the same bucket as a
+    /// value, or `None` if the value is above the `max_value`. Values which
+    /// quantize to the same value can not be told apart once recorded. Above
+    /// the linear range this is not the value reported for the bucket when
+    /// iterating over a `Slice` and by the percentiles, which is instead the
+    /// smallest value of the next bucket
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure()
+    ///     .precision(2)
+    ///     .max_value(10_000)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.quantize_value(50), Some(50));
+    /// assert_eq!(h.quantize_value(5_050), Some(5_039));
+    /// assert_eq!(h.quantize_value(5_079), Some(5_039));
+    /// assert_eq!(h.quantize_value(5_080), Some(5_080));
+    /// assert_eq!(h.quantize_value(10_001), None);
+    ///
+    /// h.increment(0, 5_050).unwrap();
+    /// assert_eq!(h.get(0, 5_039).unwrap(), 1);
+    /// assert_eq!(h.percentile(100.0).unwrap()[0], (0, 5_080));
+    /// ```
+    pub fn quantize_value(&self, value: u64) -> Option<u64> {
+        if value > self.config.max_value {
+            return None;
+        }
+        let lower = &self.properties.lower;
+        Some(lower[lower.partition_point(|&bound| bound <= value) - 1])
+    }
+
+    /// returns a 64-bit hash of the geometry, time range, and non-zero bucket
+    /// counts, which is a cheap way to check whether a `Heatmap` has changed.
+    /// It is stable across runs and platforms, so may be persisted, and
+    /// heatmaps which are equal have the same fingerprint
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let mut h = Heatmap::configure().start(0).build().unwrap();
+    /// let empty = h.fingerprint();
+    ///
+    /// h.increment(0, 1).unwrap();
+    /// assert!(h.fingerprint() != empty);
+    /// assert_eq!(h.fingerprint(), h.clone().fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = 0xcbf2_9ce4_8422_2325;
+        for value in &[
+            u64::from(self.config.precision),
+            self.config.max_value,
+            self.config.slice_duration,
+            self.config.num_slices as u64,
+            self.data.start,
+            self.data.stop,
+        ] {
+            hash = fnv1a(hash, *value);
+        }
+        for index in 0..self.config.num_slices + 1 {
+            hash = fnv1a(hash, self.slice_start(index));
+        }
+        for (index, histogram) in self.data.data.iter().enumerate() {
+            for bucket in histogram {
+                if bucket.count() > 0 {
+                    hash = fnv1a(hash, index as u64);
+                    hash = fnv1a(hash, bucket.value());
+                    hash = fnv1a(hash, bucket.count());
+                }
+            }
+        }
+        hash
+    }
+
+    /// returns an estimate of the bytes of memory used by the `Heatmap`,
+    /// including the buckets of every `Slice` and the cached geometry. Unlike
+    /// `max_memory`, which only bounds the buckets, this includes overhead
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .max_memory(1_000_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build();
+    /// assert!(h.is_err());
+    ///
+    /// let h = Heatmap::configure()
+    ///     .precision(2)
+    ///     .max_value(10_000)
+    ///     .num_slices(10)
+    ///     .start(0)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(h.memory_used() > 10 * 8 * h.histogram_buckets() as usize);
+    /// ```
+    pub fn memory_used(&self) -> usize {
+        let properties = self.properties.values.capacity() * mem::size_of::<u64>() +
+            self.properties.lower.capacity() * mem::size_of::<u64>() +
+            self.properties.offsets.capacity() * mem::size_of::<u64>();
+        let boundaries = self.config
+            .boundaries
+            .as_ref()
+            .map_or(0, |boundaries| boundaries.capacity() * mem::size_of::<u64>());
+        mem::size_of::<Heatmap>() +
+            self.data.data.capacity() * mem::size_of::<Histogram>() +
+            self.data.data.len() * self.config.histogram_memory() +
+            properties + boundaries
+    }
+
+    /// release any spare capacity held by the `Heatmap`. The buckets of each
+    /// `Histogram` are allocated up front and can not be compacted, so this
+    /// only trims the vectors which hold them and the cached geometry
+    pub fn shrink_to_fit(&mut self) {
+        self.data.data.shrink_to_fit();
+        self.properties.values.shrink_to_fit();
+        self.properties.lower.shrink_to_fit();
+        self.properties.offsets.shrink_to_fit();
+        if let Some(ref mut boundaries) = self.config.boundaries {
+            boundaries.shrink_to_fit();
+        }
     }
 
     /// returns the number of `Slice`s within `Heatmap`
     pub fn num_slices(&self) -> u64 {
         self.config.num_slices as u64
     }
+
+    /// returns the start of the time range covered by the `Heatmap`, which is
+    /// the earliest time that will be accepted
+    ///
+    /// # Example
+    /// ```
+    /// # use heatmap::Heatmap;
+    /// let h = Heatmap::configure()
+    ///     .slice_duration(1_000)
+    ///     .num_slices(10)
+    ///     .start(5_000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(h.start_time(), 5_000);
+    /// assert_eq!(h.stop_time(), 15_000);
+    /// ```
+    pub fn start_time(&self) -> u64 {
+        self.data.start
+    }
+
+    /// returns the end of the time range covered by the `Heatmap`. This is
+    /// exclusive, so samples must be earlier than it to be accepted
+    pub fn stop_time(&self) -> u64 {
+        self.data.stop
+    }
+
+    /// returns the `Config` which the `Heatmap` was built or loaded with
+    pub fn config(&self) -> Config {
+        self.config.clone()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Heatmap;
+    use super::{Config, ConfigError, Heatmap, HeatmapError, LoadError, MergeError, SyncHeatmap};
+    use std::thread;
 
     #[test]
     fn test_new_0() {
@@ -565,8 +5201,798 @@ mod tests {
         assert_eq!(h.get(30_000_000_000, 1), Ok(1));
 
         assert!(h.increment(59_999_999_999, 1).is_ok());
-        assert!(!h.increment(60_000_000_000, 1).is_ok());
-        assert!(!h.increment(60_000_000_001, 1).is_ok());
+        assert!(h.increment(60_000_000_000, 1).is_err());
+        assert!(h.increment(60_000_000_001, 1).is_err());
+
+    }
+
+    #[test]
+    fn test_increment_errors() {
+        let mut h = Heatmap::configure()
+            .max_value(1_000_000)
+            .num_slices(60)
+            .slice_duration(1_000_000_000)
+            .start(1_000_000_000)
+            .build()
+            .unwrap();
+
+        assert_eq!(h.increment(999_999_999, 1), Err(HeatmapError::SampleTooEarly));
+        assert_eq!(h.increment(61_000_000_000, 1), Err(HeatmapError::SampleTooLate));
+        assert_eq!(h.increment(1_000_000_000, 1_000_001), Err(HeatmapError::ValueOutOfRange));
+        assert_eq!(h.get(1_000_000_000, 1_000_001), Err(HeatmapError::ValueOutOfRange));
+        assert_eq!(h.entries(), 0);
+
+        assert!(h.increment(1_000_000_000, 1_000_000).is_ok());
+        assert_eq!(h.get(1_000_000_000, 1_000_000), Ok(1));
+        assert_eq!(h.entries(), 1);
+    }
+
+    #[test]
+    fn test_save() {
+        let mut h = Heatmap::configure()
+            .num_slices(10)
+            .slice_duration(1_000)
+            .start(0)
+            .build()
+            .unwrap();
+
+        h.increment_by(0, 1, 2).unwrap();
+        h.increment_by(9_999, 100, 3).unwrap();
+
+        let path = ::std::env::temp_dir().join("heatmap_test_save.txt");
+        h.save(&path).unwrap();
+
+        let loaded = Heatmap::load(&path).unwrap();
+        assert_eq!(loaded.entries(), 5);
+        assert_eq!(loaded.num_slices(), 10);
+
+        assert!(h.save(::std::env::temp_dir().join("missing").join("heatmap.txt")).is_err());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        use std::fs::File;
+        use std::io::Write;
+
+        let dir = ::std::env::temp_dir();
+
+        match Heatmap::load(dir.join("missing").join("heatmap.txt")) {
+            Err(LoadError::Io(_)) => {}
+            _ => panic!("expected io error"),
+        }
+
+        let path = dir.join("heatmap_test_load_header.txt");
+        File::create(&path).unwrap().write_all(b"3 0 1000 1000 10\n").unwrap();
+        match Heatmap::load(&path) {
+            Err(LoadError::Malformed { line: 1 }) => {}
+            _ => panic!("expected malformed header"),
+        }
+
+        let path = dir.join("heatmap_test_load_parse.txt");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"3 0 1000 1000 10 0\n0 1 1\n0 x 1\n")
+            .unwrap();
+        match Heatmap::load(&path) {
+            Err(LoadError::Parse { line: 3, .. }) => {}
+            _ => panic!("expected parse error"),
+        }
+
+        let path = dir.join("heatmap_test_load_malformed.txt");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"3 0 1000 1000 10 0\n0 1\n")
+            .unwrap();
+        match Heatmap::load(&path) {
+            Err(LoadError::Malformed { line: 2 }) => {}
+            _ => panic!("expected malformed line"),
+        }
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        match Heatmap::from_json("{\"slices\": [}") {
+            Err(LoadError::Json { offset: 12 }) => {}
+            _ => panic!("expected json error"),
+        }
+
+        match Heatmap::from_json("{\"slices\": []}") {
+            Err(LoadError::Json { .. }) => {}
+            _ => panic!("expected missing config"),
+        }
+
+        let h = Heatmap::from_json(
+            "{\"config\": {\"precision\": 3, \"max_memory\": 0, \"max_value\": 1000,
+              \"slice_duration\": 10, \"num_slices\": 2, \"start\": 0},
+              \"slices\": [{\"buckets\": [[1, 2]], \"start\": 10}]}",
+        ).unwrap();
+        assert_eq!(h.entries(), 2);
+    }
+
+    #[test]
+    fn test_circular() {
+        let mut h = Heatmap::configure()
+            .num_slices(10)
+            .slice_duration(1_000)
+            .start(0)
+            .circular(true)
+            .build()
+            .unwrap();
+
+        for slice in 0..10 {
+            h.increment(slice * 1_000, slice + 1).unwrap();
+        }
+        assert_eq!(h.entries(), 10);
+
+        // advance by three slices
+        h.increment(12_500, 100).unwrap();
+        assert_eq!(h.entries(), 8);
+        assert_eq!(h.get(3_000, 4), Ok(1));
+        assert_eq!(h.get(12_000, 100), Ok(1));
+        assert_eq!(h.increment(2_999, 1), Err(HeatmapError::SampleTooEarly));
+
+        // far in the future clears everything without looping per slice
+        h.increment(1_000_000_000_000_500, 1).unwrap();
+        assert_eq!(h.entries(), 1);
+        let slices: Vec<u64> = h.into_iter().map(|s| s.start()).collect();
+        assert_eq!(slices[9], 1_000_000_000_000_000);
+        assert_eq!(slices[0], 999_999_999_991_000);
+    }
+
+    #[test]
+    fn test_load_binary_errors() {
+        use std::fs::File;
+        use std::io::{Read, Write};
+
+        let dir = ::std::env::temp_dir();
+
+        let path = dir.join("heatmap_test_binary_magic.bin");
+        File::create(&path).unwrap().write_all(b"HMAQ\x01").unwrap();
+        match Heatmap::load_binary(&path) {
+            Err(LoadError::Binary { offset: 0 }) => {}
+            _ => panic!("expected bad magic"),
+        }
+
+        let path = dir.join("heatmap_test_binary_version.bin");
+        File::create(&path).unwrap().write_all(b"HMAP\x02").unwrap();
+        match Heatmap::load_binary(&path) {
+            Err(LoadError::UnsupportedVersion { version: 2 }) => {}
+            _ => panic!("expected unsupported version"),
+        }
+
+        let mut h = Heatmap::configure()
+            .num_slices(2)
+            .slice_duration(1_000)
+            .start(0)
+            .build()
+            .unwrap();
+        h.increment_by(1_000, 300, 200).unwrap();
+
+        let path = dir.join("heatmap_test_binary_truncated.bin");
+        h.save_binary(&path).unwrap();
+        let mut bytes = Vec::new();
+        File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+
+        let length = bytes.len();
+        File::create(&path)
+            .unwrap()
+            .write_all(&bytes[..length - 1])
+            .unwrap();
+        match Heatmap::load_binary(&path) {
+            Err(LoadError::Io(_)) => {}
+            _ => panic!("expected truncated file"),
+        }
+
+        bytes.push(0);
+        File::create(&path).unwrap().write_all(&bytes).unwrap();
+        match Heatmap::load_binary(&path) {
+            Err(LoadError::Binary { offset }) => assert_eq!(offset, length),
+            _ => panic!("expected trailing data"),
+        }
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let build = |config: super::Config| config.build().err();
+
+        assert_eq!(build(Heatmap::configure().num_slices(0)), Some(ConfigError::NumSlices));
+        assert_eq!(
+            build(Heatmap::configure().slice_duration(0)),
+            Some(ConfigError::SliceDuration)
+        );
+        assert_eq!(build(Heatmap::configure().precision(0)), Some(ConfigError::Precision));
+        assert_eq!(build(Heatmap::configure().precision(8)), Some(ConfigError::Precision));
+        assert_eq!(
+            build(Heatmap::configure().precision(4).max_value(9_999)),
+            Some(ConfigError::MaxValue)
+        );
+        assert_eq!(
+            build(Heatmap::configure().max_memory(1024).num_slices(1)),
+            Some(ConfigError::MaxMemory)
+        );
+        assert_eq!(
+            build(Heatmap::configure().max_memory(59).num_slices(60)),
+            Some(ConfigError::MaxMemory)
+        );
+        assert_eq!(
+            build(Heatmap::configure().precision(7).max_value(10_000_000).num_slices(1)),
+            None
+        );
+        assert_eq!(
+            build(Heatmap::configure().slice_duration(u64::MAX).num_slices(2)),
+            Some(ConfigError::TimeSpan)
+        );
+        assert_eq!(build(Heatmap::configure().start(u64::MAX)), Some(ConfigError::TimeSpan));
+    }
+
+    #[test]
+    fn test_subtract_incompatible() {
+        let mut a = Heatmap::configure().start(0).build().unwrap();
+        let b = Heatmap::configure().start(0).num_slices(30).build().unwrap();
+        let c = Heatmap::configure().start(0).precision(4).build().unwrap();
+
+        assert_eq!(a.subtract(&b), Err(HeatmapError::Incompatible));
+        assert_eq!(a.subtract(&c), Err(HeatmapError::Incompatible));
+    }
+
+    #[test]
+    fn test_try_merge_incompatible() {
+        let mut a = Heatmap::configure().start(0).build().unwrap();
+        let b = Heatmap::configure().start(0).precision(4).build().unwrap();
+        let c = Heatmap::configure().start(0).max_value(1_000).build().unwrap();
+
+        assert_eq!(a.try_merge(&b), Err(MergeError::Incompatible));
+        assert_eq!(a.try_merge(&c), Err(MergeError::Incompatible));
+    }
+
+    #[test]
+    fn test_merge_aligned_errors() {
+        let mut a = Heatmap::configure().start(0).build().unwrap();
+        let b = Heatmap::configure().start(0).precision(4).build().unwrap();
+        let c = Heatmap::configure().start(0).slice_duration(1_000).build().unwrap();
+
+        assert_eq!(a.merge_aligned(&b), Err(MergeError::Incompatible));
+        assert_eq!(a.merge_aligned(&c), Err(MergeError::Misaligned));
+    }
+
+    #[test]
+    fn test_span() {
+        let h = Heatmap::configure()
+            .num_slices(3)
+            .span(0, 1_000)
+            .build()
+            .unwrap();
+        assert_eq!(h.latest().unwrap().stop(), 999);
+
+        let h = Heatmap::configure().num_slices(3).span(1_000, 1_000).build();
+        assert_eq!(h.err(), Some(ConfigError::SliceDuration));
+
+        let h = Heatmap::configure().num_slices(3).span(1_000, 0).build();
+        assert_eq!(h.err(), Some(ConfigError::SliceDuration));
+    }
+
+    #[test]
+    fn test_percentiles_match_percentile() {
+        let mut h = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(4)
+            .start(0)
+            .build()
+            .unwrap();
+
+        for value in 1..1_000 {
+            h.increment(0, value).unwrap();
+            h.increment_by(1_000, value * value, value % 7).unwrap();
+        }
+        h.increment_by(2_000, 42, 3).unwrap();
+
+        let ps = [0.0, 0.1, 1.0, 10.0, 25.0, 49.9, 50.0, 75.0, 90.0, 99.0, 99.9, 100.0];
+        let all = h.percentiles(&ps).unwrap();
+        for (i, p) in ps.iter().enumerate() {
+            let single = h.percentile(*p).unwrap();
+            for (slice, row) in single.iter().zip(all.iter()) {
+                assert_eq!(slice.0, row.0);
+                assert_eq!(slice.1, row.1[i], "percentile {} at {}", p, slice.0);
+            }
+        }
+
+        assert_eq!(h.percentiles(&[50.0, 100.1]), Err(HeatmapError::InvalidPercentile));
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn test_save_png() {
+        use super::Colormap;
+        use std::fs::File;
+        use std::io::Read;
+
+        let mut h = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(10)
+            .start(0)
+            .build()
+            .unwrap();
+        h.increment(0, 1).unwrap();
+
+        let path = ::std::env::temp_dir().join("heatmap_test.png");
+        h.save_png(&path, 300, 200, Colormap::Grayscale).unwrap();
+
+        let mut bytes = Vec::new();
+        File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(&bytes[0..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&bytes[12..16], b"IHDR");
+        assert_eq!(&bytes[16..24], &[0, 0, 1, 44, 0, 0, 0, 200]);
+        assert_eq!(&bytes[bytes.len() - 8..bytes.len() - 4], b"IEND");
+
+        assert!(h.save_png(&path, 0, 200, Colormap::Grayscale).is_err());
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let build = |boundaries: Vec<u64>| Heatmap::configure().boundaries(boundaries).build();
+
+        assert_eq!(build(vec![]).err(), Some(ConfigError::Boundaries));
+        assert_eq!(build(vec![10]).err(), Some(ConfigError::Boundaries));
+        assert_eq!(build(vec![10, 10]).err(), Some(ConfigError::Boundaries));
+        assert_eq!(build(vec![10, 20, 15]).err(), Some(ConfigError::Boundaries));
+        assert_eq!(
+            Heatmap::configure()
+                .boundaries(vec![0, 1, 3])
+                .circular(true)
+                .build()
+                .err(),
+            Some(ConfigError::Boundaries)
+        );
+
+        // evenly spaced boundaries use the uniform geometry
+        let uniform = build(vec![100, 200, 300]).unwrap();
+        assert!(uniform.config().get_boundaries().is_none());
+        assert_eq!(uniform.config().get_slice_duration(), 100);
+        assert_eq!(uniform.start_time(), 100);
+        assert_eq!(uniform.stop_time(), 300);
+
+        let mut h = build(vec![0, 10, 100, 1_000, 10_000]).unwrap();
+        assert_eq!(h.config().get_boundaries(), Some(&[0, 10, 100, 1_000, 10_000][..]));
+        for time in &[0, 9, 10, 99, 100, 999, 1_000, 9_999] {
+            h.increment(*time, 1).unwrap();
+        }
+        assert_eq!(h.increment(10_000, 1), Err(HeatmapError::SampleTooLate));
+
+        let stops: Vec<u64> = h.slices().map(|slice| slice.stop()).collect();
+        assert_eq!(stops, vec![10, 100, 1_000, 10_000]);
+        assert!(h.slices().all(|slice| slice.entries() == 2));
+        assert_eq!(h.entries_in_window(50, 150), 4);
+        assert_eq!(h.entries_in_window(100, 1_000), 2);
+
+        let merged = h.downsample(2).unwrap();
+        assert_eq!(merged.config().get_boundaries(), Some(&[0, 100, 10_000][..]));
+        assert_eq!(merged.latest().unwrap().entries(), 4);
+
+        // only json can represent non-uniform boundaries
+        let path = std::env::temp_dir().join("heatmap_test_boundaries.txt");
+        assert!(h.save(&path).is_err());
+        assert!(h.save_binary(&path).is_err());
+        let loaded = Heatmap::from_json(&h.to_json()).unwrap();
+        assert_eq!(loaded.to_json(), h.to_json());
+        assert_eq!(loaded.entries_in_window(100, 1_000), 2);
+    }
+
+    #[test]
+    fn test_recount() {
+        let mut h = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(10)
+            .start(0)
+            .build()
+            .unwrap();
+        let check = |h: &Heatmap| {
+            let mut recounted = h.clone();
+            recounted.recount();
+            assert_eq!(recounted.entries(), h.entries());
+        };
+
+        for slice in 0..10 {
+            h.increment_by(slice * 1_000, slice, 7).unwrap();
+        }
+        check(&h);
+        h.clear_range(2_000, 4_000).unwrap();
+        check(&h);
+        h.decay(0.5);
+        check(&h);
+        let other = h.clone();
+        h.subtract(&other).unwrap();
+        check(&h);
+        assert_eq!(h.entries(), 0);
+    }
+
+    #[test]
+    fn test_reader_errors() {
+        use super::{HeatmapReader, HeatmapWriter};
+
+        let path = ::std::env::temp_dir().join("heatmap_test_reader.txt");
+        {
+            let mut writer = HeatmapWriter::create(&path).unwrap();
+            writer.write(0, 1, 1).unwrap();
+            writer.write(10, 2, 2).unwrap();
+        }
+
+        let records: Vec<(u64, u64, u64)> = HeatmapReader::open(&path)
+            .unwrap()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records, vec![(0, 1, 1), (10, 2, 2)]);
+
+        {
+            use std::fs::OpenOptions;
+            use std::io::Write;
+            let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(b"20 3\n").unwrap();
+        }
+
+        let mut h = Heatmap::configure().start(0).build().unwrap();
+        match HeatmapReader::open(&path).unwrap().replay(&mut h) {
+            Err(LoadError::Malformed { line: 3 }) => {}
+            _ => panic!("expected malformed record"),
+        }
+        assert_eq!(h.entries(), 3);
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut h = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(2)
+            .start(0)
+            .build()
+            .unwrap();
+        h.increment(0, 1).unwrap();
+
+        let slice = h.into_iter().next().unwrap();
+        assert_eq!(format!("{:?}", slice), "Slice { start: 0, stop: 1000, entries: 1 }");
+
+        let debug = format!("{:?}", h);
+        assert!(debug.starts_with("Heatmap { config: Config { precision: 3,"));
+        assert!(debug.ends_with("start: 0, stop: 2000, entries: 1 }"));
+    }
+
+    #[test]
+    fn test_sync_circular() {
+        let config = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(10)
+            .start(0)
+            .circular(true);
+        let h = SyncHeatmap::with_shards(config, 2).unwrap();
+
+        // each thread records into its own shard, and only one shard advances
+        thread::scope(|scope| {
+            scope.spawn(|| h.increment(5_000, 1).unwrap());
+            scope.spawn(|| h.increment(12_000, 2).unwrap());
+        });
+        assert_eq!(h.entries(), 2);
+
+        let merged = h.merged();
+        assert_eq!(merged.start_time(), 3_000);
+        assert_eq!(merged.get(5_000, 1).unwrap(), 1);
+        assert_eq!(merged.get(12_000, 2).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_from_histograms() {
+        for &(precision, max_value) in &[(1, 10), (2, 100), (2, 127), (3, 1_000), (3, 1_000_000)] {
+            let h = Heatmap::configure()
+                .precision(precision)
+                .max_value(max_value)
+                .slice_duration(1_000)
+                .num_slices(2)
+                .start(0)
+                .build()
+                .unwrap();
+            let histograms = h.clone().into_histograms();
+            let rebuilt = Heatmap::from_histograms(histograms, 0, 1_000).unwrap();
+            assert_eq!(rebuilt.config().get_precision(), precision);
+            assert_eq!(rebuilt.config().get_max_value(), max_value);
+            assert!(rebuilt == h);
+        }
+
+        let a = Heatmap::configure().precision(2).start(0).build().unwrap();
+        let b = Heatmap::configure().precision(3).start(0).build().unwrap();
+        let mut histograms = a.into_histograms();
+        histograms.push(b.into_histograms().remove(0));
+        assert_eq!(
+            Heatmap::from_histograms(histograms, 0, 1_000).err(),
+            Some(HeatmapError::Incompatible)
+        );
+        assert_eq!(
+            Heatmap::from_histograms(Vec::new(), 0, 1_000).err(),
+            Some(HeatmapError::InvalidConfig(ConfigError::NumSlices))
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        let config = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(10)
+            .start(0);
+        let mut a = config.clone().strict(true).build().unwrap();
+        let mut b = config.clone().start(5_000).build().unwrap();
+        b.increment_by(12_000, 1, 3).unwrap();
+
+        a.merge(&b);
+        assert_eq!(a.dropped(), 3);
+
+        // too early, too late, and out of range records are all counted
+        let records = [(1_000, 1, 1), (20_000, 1, 2), (2_000, u64::MAX, 4)];
+        assert_eq!(a.increment_batch(&records).unwrap(), 1);
+        assert_eq!(a.dropped(), 9);
+
+        let mut sorted = config.clone().start(1_000).strict(true).build().unwrap();
+        let records = [(0, 1, 1), (1_000, 1, 1), (2_000, u64::MAX, 2), (20_000, 1, 4), (30_000, 1, 8)];
+        assert_eq!(sorted.increment_batch_sorted(&records).unwrap(), 1);
+        assert_eq!(sorted.dropped(), 15);
+
+        // samples are only counted in strict mode
+        let mut lenient = config.build().unwrap();
+        lenient.merge(&b);
+        assert_eq!(lenient.increment_batch(&records).unwrap(), 2);
+        assert_eq!(lenient.dropped(), 0);
+
+        a.recount();
+        assert_eq!(a.dropped(), 9);
+        a.clear_counts();
+        assert_eq!(a.dropped(), 0);
+    }
+
+    #[test]
+    fn test_lower_bounds() {
+        // the index of the bucket a value is counted in, by recording it
+        fn index(h: &Heatmap, value: u64) -> usize {
+            let mut histogram = h.config.histogram().unwrap();
+            histogram.increment(value).unwrap();
+            histogram.into_iter().position(|bucket| bucket.count() > 0).unwrap()
+        }
+
+        for &(precision, max_value) in &[(1, 100), (2, 10_000), (2, 1_000_000), (3, 5_000)] {
+            let h = Heatmap::configure()
+                .precision(precision)
+                .max_value(max_value)
+                .start(0)
+                .build()
+                .unwrap();
+            let lower = &h.properties.lower;
+            assert_eq!(lower.len() as u64, h.histogram_buckets());
+            assert_eq!(lower[0], 0);
+            for (bucket, value) in lower.iter().enumerate().skip(1) {
+                assert_eq!(index(&h, *value), bucket);
+                assert!(index(&h, value - 1) < bucket);
+            }
+        }
+    }
+
+    // values past the linear range, whose reported value is counted in the
+    // next bucket up, starting with the first bucket no reported value is
+    // counted in
+    const LOG_VALUES: [u64; 4] = [129, 5_050, 9_000, 10_000];
+
+    // a config with log-linear buckets above 128
+    fn log_config() -> Config {
+        Heatmap::configure()
+            .precision(2)
+            .max_value(10_000)
+            .slice_duration(1_000)
+            .num_slices(4)
+            .start(0)
+    }
+
+    // a heatmap counting each of `LOG_VALUES` in each of its first two slices
+    fn log_heatmap() -> Heatmap {
+        let mut h = log_config().build().unwrap();
+        for value in &LOG_VALUES {
+            h.increment(0, *value).unwrap();
+            h.increment(1_000, *value).unwrap();
+        }
+        h
+    }
+
+    // checks that each of `LOG_VALUES` is found with the given count
+    fn assert_log_counts<F: Fn(u64) -> u64>(get: F, count: u64) {
+        for value in &LOG_VALUES {
+            assert_eq!(get(*value), count, "value {}", value);
+        }
+    }
+
+    #[test]
+    fn test_log_buckets_round_trip() {
+        let h = log_heatmap();
+
+        assert_eq!(Heatmap::from_json(&h.to_json()).unwrap(), h);
+
+        let path = std::env::temp_dir().join("heatmap_test_binary_log.bin");
+        h.save_binary(&path).unwrap();
+        assert_eq!(Heatmap::load_binary(&path).unwrap(), h);
+
+        // records are relative to the current start, not the configured one
+        let mut rebased = h.clone();
+        rebased.rebase(5_000).unwrap();
+        for saved in &[&h, &rebased] {
+            let mut buffer = Vec::new();
+            saved.save_to(&mut buffer).unwrap();
+            assert_eq!(Heatmap::load_from(&buffer[..]).unwrap(), **saved);
+        }
+
+        let mut cells = h.clone();
+        cells.clear();
+        for (start, value, count) in h.cells() {
+            cells.increment_by(start, value, count).unwrap();
+        }
+        assert_eq!(cells, h);
+
+        let slices = h.clone().into_iter().collect();
+        assert_eq!(Heatmap::from_slices(log_config(), slices).unwrap(), h);
+        let slices = h.clone().into_iter().collect();
+        assert_eq!(
+            Heatmap::from_slices(log_config().precision(3), slices).err(),
+            Some(HeatmapError::Incompatible)
+        );
+
+        let mut merged = log_config().build().unwrap();
+        merged.merge(&h);
+        assert_eq!(merged, h);
+        let mut merged = log_config().build().unwrap();
+        merged.merge_range(&h, 0, 4_000).unwrap();
+        assert_eq!(merged, h);
+        let mut merged = log_config().build().unwrap();
+        merged.merge_aligned(&h).unwrap();
+        assert_eq!(merged, h);
+        let mut merged = log_config().build().unwrap();
+        merged.merge_scaled(&h, 1.0);
+        assert_eq!(merged, h);
+    }
+
+    #[test]
+    fn test_log_buckets_views() {
+        let h = log_heatmap();
+        assert_log_counts(|value| h.get(1_000, value).unwrap(), 1);
+
+        let summary = h.summary();
+        assert_log_counts(|value| summary.get(value).unwrap(), 2);
+
+        let window = h.window_histogram(0, 2_000).unwrap();
+        assert_log_counts(|value| window.get(value).unwrap(), 2);
+
+        let downsampled = h.downsample(2).unwrap();
+        assert_log_counts(|value| downsampled.get(0, value).unwrap(), 2);
+
+        let resampled = h.resample(3_000, 1).unwrap();
+        assert_log_counts(|value| resampled.get(0, value).unwrap(), 2);
+
+        assert_log_counts(|value| h.get_range(0, value, value).unwrap(), 1);
+        assert_eq!(h.get_range(0, 130, 5_038).unwrap(), 0);
+        let below = h.quantize_value(9_000).unwrap() - 1;
+        assert_eq!(h.get_range(0, 5_080, below).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_log_buckets_edits() {
+        // edits must not decrement the bucket above by the reported value
+        let full = log_heatmap();
+
+        let mut h = full.clone();
+        h.decay(0.0);
+        assert_eq!(h.entries(), 0);
+        assert_eq!(h.max_bucket_count(), 0);
+
+        let mut h = full.clone();
+        h.subtract(&full).unwrap();
+        assert_eq!(h.entries(), 0);
+        assert_eq!(h.max_bucket_count(), 0);
+
+        let mut h = full.clone();
+        h.retain(|_, value, _| value < 5_000);
+        assert_eq!(h.entries(), 2);
+        assert_eq!(h.max_bucket_count(), 1);
+
+        let mut h = full.clone();
+        h.map_counts(|count| count * 2);
+        assert_eq!(h.entries(), 16);
+        assert_eq!(h.max_bucket_count(), 2);
+    }
+
+    #[test]
+    fn test_quantize_value() {
+        let mut h = Heatmap::configure()
+            .precision(2)
+            .max_value(10_000)
+            .slice_duration(1_000)
+            .num_slices(1)
+            .start(0)
+            .build()
+            .unwrap();
+        for value in (0..10_001).step_by(7) {
+            let quantized = h.quantize_value(value).unwrap();
+            assert!(quantized <= value);
+            assert!(h.value_boundaries().binary_search(&quantized).is_ok());
+            assert_eq!(h.quantize_value(quantized), Some(quantized));
+            h.increment(0, value).unwrap();
+            assert!(h.get(0, quantized).unwrap() > 0);
+            assert_eq!(h.get(0, quantized).unwrap(), h.get(0, value).unwrap());
+        }
+        assert_eq!(h.quantize_value(129), Some(129));
+    }
+
+    #[test]
+    fn test_advance_overflow() {
+        let mut h = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(10)
+            .start(0)
+            .circular(true)
+            .strict(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(h.increment(u64::MAX, 1), Err(HeatmapError::SampleTooLate));
+        assert!(h.stop_time() > u64::MAX - 1_000);
+        assert_eq!(h.stop_time() - h.start_time(), 10_000);
+        h.increment(h.stop_time() - 1, 1).unwrap();
+
+        let records = [(h.stop_time() - 1, 1, 1), (u64::MAX, 1, 2)];
+        assert_eq!(h.increment_batch_sorted(&records).unwrap(), 1);
+        assert_eq!(h.entries(), 2);
+        assert_eq!(h.dropped(), 3);
+
+        // growing would carry the stop past u64::MAX
+        h.append(1);
+        assert_eq!(h.num_slices(), 10);
+        assert_eq!(h.stop_time() - h.start_time(), 10_000);
+    }
+
+    #[test]
+    fn test_append_overflow() {
+        let mut h = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(10)
+            .start(u64::MAX - 10_000)
+            .build()
+            .unwrap();
+
+        h.append(1);
+        assert_eq!(h.num_slices(), 10);
+        assert_eq!(h.stop_time(), u64::MAX);
+        h.increment(u64::MAX - 1, 1).unwrap();
+        assert_eq!(h.entries(), 1);
+    }
+
+    #[test]
+    fn test_merge_all_threads() {
+        // enough buckets to merge on several threads
+        let config = Heatmap::configure()
+            .slice_duration(1_000)
+            .num_slices(64)
+            .start(0);
+        let mut heatmaps = Vec::new();
+        for i in 0..2 {
+            let mut h = config.clone().build().unwrap();
+            for slice in 0..64 {
+                h.increment(slice * 1_000, 5_050 + i).unwrap();
+            }
+            heatmaps.push(h);
+        }
+
+        let mut expected = heatmaps[0].clone();
+        expected.merge_aligned(&heatmaps[1]).unwrap();
+        assert_eq!(Heatmap::merge_all(&heatmaps).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_decay_large_count() {
+        let mut h = Heatmap::configure().start(0).build().unwrap();
+        h.increment_by(0, 1, (1 << 54) - 1).unwrap();
 
+        h.decay(1.0);
+        assert_eq!(h.get(0, 1).unwrap(), (1 << 54) - 1);
+        h.decay(0.0);
+        assert_eq!(h.entries(), 0);
     }
 }
@@ -0,0 +1,96 @@
+// a minimal PNG encoder for 8-bit RGB images. The image data is stored in
+// uncompressed deflate blocks so that no compression library is required
+
+use std::io;
+use std::io::Write;
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+// the largest payload of a single uncompressed deflate block
+const MAX_BLOCK: usize = 65_535;
+
+/// write an RGB image, where `pixels` holds 3 bytes per pixel in row-major
+/// order starting from the top left
+pub fn write_rgb<W: Write>(writer: &mut W, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let stride = width as usize * 3;
+    debug_assert_eq!(pixels.len(), stride * height as usize);
+
+    writer.write_all(SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // bit depth 8, color type RGB, default compression, filter, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(writer, b"IHDR", &header)?;
+
+    // each scanline is prefixed with a filter type of 0 (none)
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in pixels.chunks(stride) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    write_chunk(writer, b"IDAT", &zlib_stored(&raw))?;
+
+    write_chunk(writer, b"IEND", &[])
+}
+
+// write a single chunk with its length and checksum
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(crc32(0xffff_ffff, kind), data) ^ 0xffff_ffff;
+    writer.write_all(&crc.to_be_bytes())
+}
+
+// wrap the data in a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len() / MAX_BLOCK + 1;
+    let mut stream = Vec::with_capacity(data.len() + blocks * 5 + 6);
+
+    // deflate with a 32KB window, and no preset dictionary
+    stream.extend_from_slice(&[0x78, 0x01]);
+
+    let mut chunks = data.chunks(MAX_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let length = chunk.len() as u16;
+        stream.push(if last { 1 } else { 0 });
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+// update a running CRC-32 (as used by PNG) with more data
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+// the Adler-32 checksum of the uncompressed data, as used by zlib
+fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for byte in data {
+        a = (a + u32::from(*byte)) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    (b << 16) | a
+}